@@ -1,5 +1,9 @@
 mod modules;
-use modules::{auth, download, installations, mods, news, servers, versions};
+use modules::{
+    auth, backups, bisect, cache, crashes, dependencies, download, gamelogs, http, installations,
+    jobs, modpacks, mods, news, processes, servers, updates, vault, versions,
+};
+use tauri::Manager;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -13,16 +17,31 @@ pub fn run() {
         .plugin(tauri_plugin_zustand::init())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_window_state::Builder::default().build())
+        .manage(jobs::DownloadManager::default())
+        .manage(processes::GameProcesses::default())
+        .manage(vault::Vault::default())
+        .setup(|app| {
+            app.manage(http::Http::load(app.handle()));
+            auth::spawn_session_validator(app.handle().clone());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             // Authorization
             auth::login,
             auth::verify,
+            vault::forget_account,
+            // Network settings
+            http::get_http_config,
+            http::set_http_config,
+            cache::clear_http_cache,
             // News
             news::fetch_news,
             // Mods
             mods::fetch_mod_tags,
             mods::fetch_mods,
             mods::fetch_mod_info,
+            mods::get_best_release,
+            mods::install_mod,
             mods::fetch_authors,
             mods::get_mods,
             mods::get_mod_configs,
@@ -30,11 +49,21 @@ pub fn run() {
             mods::get_installation_mods,
             mods::add_mod_to_installation,
             mods::remove_mod_from_installation,
+            mods::enable_mod,
+            mods::disable_mod,
             mods::save_mod_config,
             // Download
             download::get_download_links,
             download::get_download_link,
             download::download_and_maybe_extract,
+            download::get_download_info,
+            // Download jobs
+            jobs::enqueue_download,
+            jobs::list_downloads,
+            jobs::pause_download,
+            jobs::resume_download,
+            jobs::cancel_download,
+            jobs::clear_finished_downloads,
             // Versions
             versions::fetch_versions,
             versions::get_installed_versions,
@@ -45,6 +74,36 @@ pub fn run() {
             installations::initialize_game,
             installations::reveal_in_file_explorer,
             installations::remove_installation,
+            installations::clone_installation,
+            // Save backups
+            backups::backup_saves,
+            backups::list_save_backups,
+            backups::restore_save_backup,
+            backups::prune_save_backups,
+            // Running games
+            processes::list_running_games,
+            processes::kill_game,
+            // Launch logs
+            gamelogs::list_launch_logs,
+            gamelogs::read_launch_log,
+            // Crash reports
+            crashes::analyze_crash,
+            // Bisection
+            bisect::get_bisect,
+            bisect::start_bisect,
+            bisect::launch_bisect,
+            bisect::answer_bisect,
+            bisect::stop_bisect,
+            // Dependencies
+            dependencies::resolve_dependencies,
+            // Mod updates
+            updates::get_update_plan,
+            updates::apply_mod_update,
+            updates::rollback_mod_update,
+            // Modpacks
+            modpacks::export_modpack,
+            modpacks::read_modpack,
+            modpacks::import_modpack,
             // Servers
             servers::fetch_public_servers,
         ])
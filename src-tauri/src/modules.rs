@@ -1,8 +1,22 @@
 pub mod auth;
+pub mod backups;
+pub mod bisect;
+pub mod cache;
+pub mod crashes;
+pub mod dependencies;
 pub mod download;
 pub mod errors;
+pub mod gamelogs;
+pub mod http;
 pub mod installations;
+pub mod jobs;
+pub mod modpacks;
 pub mod mods;
 pub mod news;
+pub mod processes;
 pub mod servers;
+pub mod store;
+pub mod updates;
+pub mod util;
+pub mod vault;
 pub mod versions;
@@ -1,8 +1,20 @@
-use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE, HOST};
+use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
 use serde::{Deserialize, Serialize};
-use tauri::command;
+use serde_json::{json, Value};
+use std::time::Duration;
+use tauri::{command, AppHandle, Emitter, Runtime};
+use tauri_plugin_zustand::ManagerExt;
 
-use super::errors::UiError;
+use super::errors::{ErrorCode, UiError};
+use super::http::{self, Service};
+use super::vault::{self, Secrets};
+
+const ACCOUNTS_STORE: &str = "accounts";
+// Keys older versions kept in the accounts store in plain text
+const SECRET_KEYS: [&str; 2] = ["sessionkey", "sessionsignature"];
+// Give the frontend time to load the accounts store before the first check
+const SESSION_CHECK_DELAY: Duration = Duration::from_secs(15);
+const SESSION_CHECK_INTERVAL: Duration = Duration::from_secs(30 * 60);
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Account {
@@ -35,64 +47,246 @@ pub struct AuthVerifyResponse {
     pub reason: Option<String>,
 }
 
-#[command]
-pub async fn verify(uid: String, sessionkey: String) -> Result<AuthVerifyResponse, UiError> {
-    let client = reqwest::Client::new();
+fn secrets_of(user: &Value) -> Option<Secrets> {
+    let sessionkey = user.get("sessionkey")?.as_str().filter(|s| !s.is_empty())?;
+    Some(Secrets {
+        sessionkey: sessionkey.to_string(),
+        sessionsignature: user
+            .get("sessionsignature")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string(),
+    })
+}
+
+fn strip_secrets(user: &mut Value) -> bool {
+    let Some(obj) = user.as_object_mut() else {
+        return false;
+    };
+    let mut stripped = false;
+    for key in SECRET_KEYS {
+        stripped |= obj.remove(key).is_some();
+    }
+    stripped
+}
+
+// Moves session keys still stored in plain text in the accounts store into the vault
+fn migrate_plaintext_secrets<R: Runtime>(app: &AppHandle<R>) -> Result<(), UiError> {
+    let mut users = app
+        .zustand()
+        .get(ACCOUNTS_STORE, "users")
+        .unwrap_or(Value::Null);
+    let mut selected = app
+        .zustand()
+        .get(ACCOUNTS_STORE, "selectedUser")
+        .unwrap_or(Value::Null);
+
+    let mut users_changed = false;
+    for user in users.as_array_mut().into_iter().flatten() {
+        let uid = user.get("uid").and_then(|v| v.as_str()).map(String::from);
+        if let (Some(uid), Some(secrets)) = (uid, secrets_of(user)) {
+            vault::store_secrets(app, &uid, secrets)?;
+        }
+        users_changed |= strip_secrets(user);
+    }
+    if users_changed {
+        app.zustand()
+            .set(ACCOUNTS_STORE, "users", users)
+            .map_err(|e| UiError::io("Failed to save accounts", e))?;
+    }
+
+    let uid = selected
+        .get("uid")
+        .and_then(|v| v.as_str())
+        .map(String::from);
+    if let (Some(uid), Some(secrets)) = (uid, secrets_of(&selected)) {
+        vault::store_secrets(app, &uid, secrets)?;
+    }
+    if strip_secrets(&mut selected) {
+        app.zustand()
+            .set(ACCOUNTS_STORE, "selectedUser", selected)
+            .map_err(|e| UiError::io("Failed to save accounts", e))?;
+    }
+    Ok(())
+}
+
+fn account_secrets<R: Runtime>(app: &AppHandle<R>, uid: &str) -> Result<Secrets, UiError> {
+    migrate_plaintext_secrets(app)?;
+    vault::get_secrets(app, uid)?.ok_or_else(|| {
+        UiError::new(
+            ErrorCode::AuthRequired,
+            "No stored session for this account, please sign in again",
+        )
+    })
+}
+
+// The account selected in the UI, completed with its secrets from the vault
+pub fn selected_account<R: Runtime>(app: &AppHandle<R>) -> Result<Account, UiError> {
+    let selected = app
+        .zustand()
+        .get(ACCOUNTS_STORE, "selectedUser")
+        .unwrap_or(Value::Null);
+    let uid = selected
+        .get("uid")
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.is_empty())
+        .ok_or_else(|| UiError::new(ErrorCode::AuthRequired, "No account selected"))?
+        .to_string();
+    let secrets = account_secrets(app, &uid)?;
+    Ok(Account {
+        playername: selected
+            .get("playername")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string(),
+        uid,
+        sessionkey: secrets.sessionkey,
+        sessionsignature: secrets.sessionsignature,
+    })
+}
+
+// Asks the auth server whether a session is still alive, InvalidSession when it is not
+pub async fn validate_session<R: Runtime>(
+    app: &AppHandle<R>,
+    uid: &str,
+    sessionkey: &str,
+) -> Result<AuthVerifyResponse, UiError> {
+    let api = http::api(app);
     let mut headers = HeaderMap::new();
     headers.insert(
         CONTENT_TYPE,
         HeaderValue::from_static("application/x-www-form-urlencoded"),
     );
-    headers.insert(HOST, HeaderValue::from_static("auth3.vintagestory.at"));
 
-    let params = [("uid", uid.as_str()), ("sessionkey", sessionkey.as_str())];
+    let params = [("uid", uid), ("sessionkey", sessionkey)];
 
-    let res = client
-        .post("https://auth3.vintagestory.at/clientvalidate")
+    let res = api
+        .client()
+        .post(api.url(Service::Auth, "/clientvalidate"))
         .headers(headers)
         .form(&params)
         .send()
-        .await
-        .map_err(|e| format!("Request error: {e}"))?;
+        .await?;
 
     if !res.status().is_success() {
-        return Err(UiError {
-            name: "http_error".into(),
-            message: format!("HTTP error: {}", res.status()),
-        });
+        return Err(UiError::http_status(res.status()));
     }
 
-    let json_response = res
-        .json::<AuthVerifyResponse>()
-        .await
-        .map_err(|e| format!("Parse error: {e}"))?;
+    let json_response = res.json::<AuthVerifyResponse>().await?;
 
     if json_response.valid == 0 {
-        return Err(UiError {
-            name: "invalid_session".into(),
-            message: json_response
+        return Err(UiError::new(
+            ErrorCode::InvalidSession,
+            json_response
                 .reason
                 .unwrap_or("Invalid session".to_string()),
-        });
+        ));
     }
 
     Ok(json_response)
 }
 
+// Launching with a dead session only fails inside the game, so check it up front.
+// Network trouble is not a reason to block playing, the game validates again anyway.
+pub async fn ensure_valid_session<R: Runtime>(
+    app: &AppHandle<R>,
+    account: &Account,
+) -> Result<(), UiError> {
+    match validate_session(app, &account.uid, &account.sessionkey).await {
+        Ok(_) => Ok(()),
+        Err(e) if e.code == ErrorCode::InvalidSession => Err(UiError::new(
+            ErrorCode::AuthRequired,
+            format!(
+                "The session of {} has expired, please sign in again",
+                account.playername
+            ),
+        )
+        .with_details(json!({ "uid": account.uid, "reason": e.message }))),
+        Err(e) => {
+            log::warn!("Could not validate session before launch: {e}");
+            Ok(())
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionStatus {
+    pub uid: String,
+    pub playername: String,
+    // "valid" | "invalid" | "missing" | "unknown" (the check itself failed)
+    pub status: &'static str,
+    pub reason: Option<String>,
+}
+
+async fn check_accounts<R: Runtime>(app: &AppHandle<R>) {
+    // The frontend loads the accounts store, until then there is nothing to check
+    let Some(Value::Array(users)) = app.zustand().get(ACCOUNTS_STORE, "users") else {
+        return;
+    };
+    for user in users {
+        let Some(uid) = user.get("uid").and_then(|v| v.as_str()) else {
+            continue;
+        };
+        let playername = user
+            .get("playername")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string();
+        let (status, reason) = match account_secrets(app, uid) {
+            Ok(secrets) => match validate_session(app, uid, &secrets.sessionkey).await {
+                Ok(_) => ("valid", None),
+                Err(e) if e.code == ErrorCode::InvalidSession => ("invalid", Some(e.message)),
+                Err(e) => ("unknown", Some(e.message)),
+            },
+            Err(e) if e.code == ErrorCode::AuthRequired => ("missing", Some(e.message)),
+            Err(e) => ("unknown", Some(e.message)),
+        };
+        app.emit(
+            "auth://status",
+            SessionStatus {
+                uid: uid.to_string(),
+                playername,
+                status,
+                reason,
+            },
+        )
+        .ok();
+    }
+}
+
+// Periodically validates every stored account and reports each on "auth://status"
+pub fn spawn_session_validator<R: Runtime>(app: AppHandle<R>) {
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(SESSION_CHECK_DELAY).await;
+        loop {
+            if !http::api(&app).offline() {
+                check_accounts(&app).await;
+            }
+            tokio::time::sleep(SESSION_CHECK_INTERVAL).await;
+        }
+    });
+}
+
+#[command]
+pub async fn verify(app: AppHandle, uid: String) -> Result<AuthVerifyResponse, UiError> {
+    let sessionkey = account_secrets(&app, &uid)?.sessionkey;
+    validate_session(&app, &uid, &sessionkey).await
+}
+
 #[command]
 pub async fn login(
+    app: AppHandle,
     email: String,
     password: String,
     totpcode: Option<String>,
     prelogintoken: Option<String>,
 ) -> Result<GameLoginResponse, UiError> {
-    let client = reqwest::Client::new();
+    let api = http::api(&app);
     let mut headers = HeaderMap::new();
     headers.insert(
         CONTENT_TYPE,
         HeaderValue::from_static("application/x-www-form-urlencoded"),
     );
-    headers.insert(HOST, HeaderValue::from_static("auth3.vintagestory.at"));
 
     let params = [
         ("email", email.as_str()),
@@ -102,41 +296,48 @@ pub async fn login(
         ("gameloginversion", "1.21.0"),
     ];
 
-    let res = client
-        .post("https://auth3.vintagestory.at/v2/gamelogin")
+    let res = api
+        .client()
+        .post(api.url(Service::Auth, "/v2/gamelogin"))
         .headers(headers)
         .form(&params)
         .send()
-        .await
-        .map_err(|e| format!("Request error: {e}"))?;
+        .await?;
 
     if !res.status().is_success() {
-        return Err(UiError {
-            name: "http_error".into(),
-            message: format!("HTTP error: {}", res.status()),
-        });
+        return Err(UiError::http_status(res.status()));
     }
 
-    let json_response = res
-        .json::<GameLoginResponse>()
-        .await
-        .map_err(|e| format!("JSON error: {e}"))?;
+    let json_response = res.json::<GameLoginResponse>().await?;
 
     if json_response.valid == 0 {
-        if json_response.prelogintoken.is_some() {
-            return Err(UiError {
-                name: json_response
-                    .prelogintoken
-                    .unwrap_or("prelogin_required".to_string()),
-                message: json_response
+        // The message stays the raw auth server reason, e.g. "requiretotpcode"
+        if let Some(prelogintoken) = json_response.prelogintoken {
+            return Err(UiError::new(
+                ErrorCode::TotpRequired,
+                json_response
                     .reason
                     .unwrap_or("Pre-login required".to_string()),
-            });
+            )
+            .with_details(json!({ "prelogintoken": prelogintoken })));
         }
-        return Err(UiError {
-            name: "invalid_login".into(),
-            message: json_response.reason.unwrap_or("Invalid login".to_string()),
-        });
+        return Err(UiError::new(
+            ErrorCode::InvalidLogin,
+            json_response.reason.unwrap_or("Invalid login".to_string()),
+        ));
+    }
+
+    // Secrets go to the vault, the frontend only keeps the account reference
+    let mut json_response = json_response;
+    if let (Some(uid), Some(sessionkey)) = (&json_response.uid, json_response.sessionkey.take()) {
+        vault::store_secrets(
+            &app,
+            uid,
+            Secrets {
+                sessionkey,
+                sessionsignature: json_response.sessionsignature.take().unwrap_or_default(),
+            },
+        )?;
     }
 
     Ok(json_response)
@@ -0,0 +1,348 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+use tauri::{command, AppHandle, Manager, Runtime};
+use zip::{write::SimpleFileOptions, ZipArchive, ZipWriter};
+
+use super::errors::{ErrorCode, UiError};
+use super::processes;
+use super::store;
+use super::util::{self, millis, zip_error};
+
+// Metadata entry stored inside every backup zip
+const METADATA_NAME: &str = "backup.json";
+const WORLD_EXTENSION: &str = "vcdbs";
+const DEFAULT_KEEP: usize = 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorldInfo {
+    pub name: String,
+    pub size: u64,
+    // Milliseconds since the epoch
+    pub modified: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupMetadata {
+    pub created_at: u64,
+    // "manual" | "auto" | "restore"
+    pub reason: String,
+    pub worlds: Vec<WorldInfo>,
+    // Fingerprint of Mods/ at backup time, used to tell whether mods changed since
+    pub mods_fingerprint: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SaveBackup {
+    pub name: String,
+    pub path: String,
+    pub size: u64,
+    #[serde(flatten)]
+    pub metadata: BackupMetadata,
+}
+
+fn backups_dir<R: Runtime>(app: &AppHandle<R>, installation_id: u64) -> Result<PathBuf, UiError> {
+    Ok(app
+        .path()
+        .app_data_dir()
+        .map_err(|e| UiError::io("Failed to resolve app data dir", e))?
+        .join("backups")
+        .join(installation_id.to_string()))
+}
+
+fn valid_name(name: &str) -> bool {
+    util::valid_generated_name(name, "saves-", ".zip")
+}
+
+fn worlds(installation_path: &Path) -> Result<Vec<(PathBuf, WorldInfo)>, UiError> {
+    let saves = installation_path.join("Saves");
+    if !saves.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut worlds = vec![];
+    for entry in fs::read_dir(&saves).map_err(|e| UiError::io("Failed to read Saves", e))? {
+        let entry = entry.map_err(|e| UiError::io("Failed to read Saves", e))?;
+        let path = entry.path();
+        let is_world = path
+            .extension()
+            .and_then(|s| s.to_str())
+            .map(|ext| ext.eq_ignore_ascii_case(WORLD_EXTENSION))
+            .unwrap_or(false);
+        if !is_world || !path.is_file() {
+            continue;
+        }
+        let meta = entry
+            .metadata()
+            .map_err(|e| UiError::io("Failed to read world", e))?;
+        worlds.push((
+            path.clone(),
+            WorldInfo {
+                name: entry.file_name().to_string_lossy().into_owned(),
+                size: meta.len(),
+                modified: meta.modified().map(millis).unwrap_or(0),
+            },
+        ));
+    }
+    worlds.sort_by(|a, b| a.1.name.cmp(&b.1.name));
+    Ok(worlds)
+}
+
+// Cheap fingerprint from names, sizes and modification times of everything in Mods/
+pub fn mods_fingerprint(installation_path: &Path) -> String {
+    let mut entries: Vec<String> = fs::read_dir(installation_path.join("Mods"))
+        .map(|rd| {
+            rd.flatten()
+                .filter_map(|entry| {
+                    let meta = entry.metadata().ok()?;
+                    Some(format!(
+                        "{}:{}:{}",
+                        entry.file_name().to_string_lossy(),
+                        meta.len(),
+                        meta.modified().map(millis).unwrap_or(0)
+                    ))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    entries.sort();
+    let mut hasher = Sha256::new();
+    for entry in entries {
+        hasher.update(entry.as_bytes());
+        hasher.update(b"\n");
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+fn read_backup(path: &Path) -> Result<SaveBackup, UiError> {
+    let file = File::open(path).map_err(|e| UiError::io("Failed to open backup", e))?;
+    let size = file
+        .metadata()
+        .map_err(|e| UiError::io("Failed to open backup", e))?
+        .len();
+    let mut archive = ZipArchive::new(file).map_err(|e| zip_error("Failed to read backup", e))?;
+    let mut entry = archive
+        .by_name(METADATA_NAME)
+        .map_err(|e| zip_error("Backup has no metadata", e))?;
+    let mut json = String::new();
+    entry
+        .read_to_string(&mut json)
+        .map_err(|e| UiError::io("Failed to read backup", e))?;
+    Ok(SaveBackup {
+        name: path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned(),
+        path: path.to_string_lossy().into_owned(),
+        size,
+        metadata: serde_json::from_str(&json)
+            .map_err(|e| UiError::parse("Invalid backup metadata", e))?,
+    })
+}
+
+// Newest first
+fn collect_backups(dir: &Path) -> Result<Vec<SaveBackup>, UiError> {
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut backups = vec![];
+    for entry in fs::read_dir(dir).map_err(|e| UiError::io("Failed to read backups", e))? {
+        let entry = entry.map_err(|e| UiError::io("Failed to read backups", e))?;
+        if !valid_name(&entry.file_name().to_string_lossy()) {
+            continue;
+        }
+        match read_backup(&entry.path()) {
+            Ok(backup) => backups.push(backup),
+            Err(e) => log::warn!("Skipping unreadable backup: {e}"),
+        }
+    }
+    backups.sort_by_key(|b| std::cmp::Reverse(b.metadata.created_at));
+    Ok(backups)
+}
+
+// Zips all worlds of an installation, None when there is nothing to back up
+pub fn create_backup<R: Runtime>(
+    app: &AppHandle<R>,
+    installation_id: u64,
+    reason: &str,
+) -> Result<Option<SaveBackup>, UiError> {
+    let installation = store::find(app, installation_id)?;
+    let installation_path = PathBuf::from(&installation.path);
+    let worlds = worlds(&installation_path)?;
+    if worlds.is_empty() {
+        return Ok(None);
+    }
+    let dir = backups_dir(app, installation_id)?;
+    fs::create_dir_all(&dir).map_err(|e| UiError::io("Failed to create backups dir", e))?;
+
+    let metadata = BackupMetadata {
+        created_at: millis(SystemTime::now()),
+        reason: reason.to_string(),
+        worlds: worlds.iter().map(|(_, info)| info.clone()).collect(),
+        mods_fingerprint: mods_fingerprint(&installation_path),
+    };
+    let path = dir.join(format!("saves-{}.zip", metadata.created_at));
+    util::write_atomic_with(&path, |file| {
+        let mut zip = ZipWriter::new(file);
+        let options = SimpleFileOptions::default().large_file(true);
+        for (world, info) in &worlds {
+            zip.start_file(info.name.as_str(), options)
+                .map_err(|e| zip_error("Failed to write backup", e))?;
+            let mut source =
+                File::open(world).map_err(|e| UiError::io("Failed to read world", e))?;
+            io::copy(&mut source, &mut zip)
+                .map_err(|e| UiError::io("Failed to write backup", e))?;
+        }
+        zip.start_file(METADATA_NAME, SimpleFileOptions::default())
+            .map_err(|e| zip_error("Failed to write backup", e))?;
+        zip.write_all(serde_json::to_string_pretty(&metadata)?.as_bytes())
+            .map_err(|e| UiError::io("Failed to write backup", e))?;
+        zip.finish()
+            .map_err(|e| zip_error("Failed to write backup", e))?;
+        Ok(())
+    })?;
+    read_backup(&path).map(Some)
+}
+
+// Backs up before launching, but only when mods changed since the newest backup
+pub fn backup_if_mods_changed<R: Runtime>(
+    app: &AppHandle<R>,
+    installation_id: u64,
+) -> Result<Option<SaveBackup>, UiError> {
+    let installation = store::find(app, installation_id)?;
+    let fingerprint = mods_fingerprint(Path::new(&installation.path));
+    let newest = collect_backups(&backups_dir(app, installation_id)?)?
+        .into_iter()
+        .next();
+    if newest.is_some_and(|b| b.metadata.mods_fingerprint == fingerprint) {
+        return Ok(None);
+    }
+    create_backup(app, installation_id, "auto")
+}
+
+#[command]
+pub async fn backup_saves(app: AppHandle, installation_id: u64) -> Result<SaveBackup, UiError> {
+    create_backup(&app, installation_id, "manual")?
+        .ok_or_else(|| UiError::not_found("No worlds to back up"))
+}
+
+#[command]
+pub fn list_save_backups(app: AppHandle, installation_id: u64) -> Result<Vec<SaveBackup>, UiError> {
+    collect_backups(&backups_dir(&app, installation_id)?)
+}
+
+// Restores all worlds of a backup, or only the named ones. Current worlds are backed up first.
+#[command]
+pub async fn restore_save_backup(
+    app: AppHandle,
+    installation_id: u64,
+    name: String,
+    worlds: Option<Vec<String>>,
+) -> Result<Vec<String>, UiError> {
+    if !valid_name(&name) {
+        return Err(UiError::new(
+            ErrorCode::InvalidParams,
+            format!("Invalid backup name: {name}"),
+        ));
+    }
+    // The game keeps its open worlds locked and would overwrite them on exit
+    if processes::is_running(&app, installation_id) {
+        return Err(UiError::new(
+            ErrorCode::InvalidState,
+            "Close the game before restoring its saves",
+        ));
+    }
+    let path = backups_dir(&app, installation_id)?.join(&name);
+    let backup = read_backup(&path)?;
+    let installation = store::find(&app, installation_id)?;
+    let saves = PathBuf::from(&installation.path).join("Saves");
+
+    // Everything is checked before the "restore" backup is taken
+    if let Some(unknown) = worlds
+        .iter()
+        .flatten()
+        .find(|name| !backup.metadata.worlds.iter().any(|w| &w.name == *name))
+    {
+        return Err(UiError::new(
+            ErrorCode::InvalidParams,
+            format!("World {unknown} is not in backup {name}"),
+        ));
+    }
+    let selected: Vec<String> = backup
+        .metadata
+        .worlds
+        .into_iter()
+        .map(|w| w.name)
+        .filter(|world| worlds.as_ref().is_none_or(|w| w.contains(world)))
+        .collect();
+    // World names come from file names, never from nested paths
+    if let Some(unsafe_name) = selected
+        .iter()
+        .find(|world| Path::new(world).file_name().and_then(|s| s.to_str()) != Some(world))
+    {
+        return Err(UiError::new(
+            ErrorCode::ZipSlip,
+            format!("unsafe world name in backup: {unsafe_name}"),
+        ));
+    }
+    let file = File::open(&path).map_err(|e| UiError::io("Failed to open backup", e))?;
+    let mut archive = ZipArchive::new(file).map_err(|e| zip_error("Failed to read backup", e))?;
+    if let Some(missing) = selected
+        .iter()
+        .find(|world| archive.index_for_name(world).is_none())
+    {
+        return Err(UiError::new(
+            ErrorCode::InvalidData,
+            format!("World {missing} is missing from backup {name}"),
+        ));
+    }
+
+    fs::create_dir_all(&saves).map_err(|e| UiError::io("Failed to create Saves", e))?;
+    create_backup(&app, installation_id, "restore")?;
+
+    for world in &selected {
+        let mut entry = archive
+            .by_name(world)
+            .map_err(|e| zip_error("World missing from backup", e))?;
+        util::write_atomic_with(&saves.join(world), |out| {
+            io::copy(&mut entry, out)
+                .map(|_| ())
+                .map_err(|e| UiError::io("Failed to restore world", e))
+        })?;
+    }
+    Ok(selected)
+}
+
+// Keeps the newest `keep` backups (10 by default) and drops anything older than `max_age_days`
+#[command]
+pub fn prune_save_backups(
+    app: AppHandle,
+    installation_id: u64,
+    keep: Option<usize>,
+    max_age_days: Option<u64>,
+) -> Result<Vec<String>, UiError> {
+    let backups = collect_backups(&backups_dir(&app, installation_id)?)?;
+    let cutoff = max_age_days.map(|days| {
+        millis(SystemTime::now())
+            .saturating_sub(Duration::from_secs(days * 24 * 60 * 60).as_millis() as u64)
+    });
+    let mut removed = vec![];
+    for (i, backup) in backups.into_iter().enumerate() {
+        let too_many = i >= keep.unwrap_or(DEFAULT_KEEP);
+        let too_old = cutoff.is_some_and(|cutoff| backup.metadata.created_at < cutoff);
+        if too_many || too_old {
+            fs::remove_file(&backup.path).map_err(|e| UiError::io("Failed to remove backup", e))?;
+            removed.push(backup.name);
+        }
+    }
+    Ok(removed)
+}
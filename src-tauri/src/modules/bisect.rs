@@ -0,0 +1,387 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+use tauri::{command, AppHandle, Emitter, Manager, Runtime};
+
+use super::backups;
+use super::dependencies::BUILTIN_MODS;
+use super::errors::{ErrorCode, UiError};
+use super::installations::{self, PlayGameParams};
+use super::mods::{self, DISABLED_DIR};
+use super::processes::{self, GameExit};
+use super::store;
+use super::util::{self, now_millis};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BisectOutcome {
+    Worked,
+    Crashed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BisectStatus {
+    Testing,
+    // The culprits are known and stay disabled
+    Found,
+    // Every suspect was cleared, the crash needs more than one mod or doesn't reproduce
+    Inconclusive,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BisectMod {
+    pub modid: String,
+    pub name: String,
+    // Mods are tracked by file name, it stays the same while moving between folders
+    pub filename: String,
+    // Lowercased modids, without the builtin ones
+    pub dependencies: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BisectRound {
+    pub enabled: Vec<String>,
+    pub outcome: BisectOutcome,
+}
+
+// Everything below is file names of `mods`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BisectState {
+    pub installation_id: u64,
+    // Milliseconds since the epoch
+    pub started_at: u64,
+    pub status: BisectStatus,
+    // The mods that were enabled when the bisection started, the only ones ever moved
+    pub mods: Vec<BisectMod>,
+    // May still be the culprit
+    pub suspects: Vec<String>,
+    // Enabled for the current launch
+    pub testing: Vec<String>,
+    pub rounds: Vec<BisectRound>,
+    // How the last launch ended, for the UI to offer as the answer
+    pub observed: Option<BisectOutcome>,
+    pub culprits: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BisectObserved {
+    pub installation_id: u64,
+    pub outcome: BisectOutcome,
+}
+
+fn state_path<R: Runtime>(app: &AppHandle<R>, installation_id: u64) -> Result<PathBuf, UiError> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join("bisect").join(format!("{installation_id}.json")))
+        .map_err(|e| UiError::io("Failed to resolve app data dir", e))
+}
+
+fn load<R: Runtime>(
+    app: &AppHandle<R>,
+    installation_id: u64,
+) -> Result<Option<BisectState>, UiError> {
+    let path = state_path(app, installation_id)?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let text =
+        fs::read_to_string(&path).map_err(|e| UiError::io("Failed to read bisection state", e))?;
+    serde_json::from_str(&text)
+        .map(Some)
+        .map_err(|e| UiError::parse("Bisection state is corrupt", e))
+}
+
+fn save<R: Runtime>(app: &AppHandle<R>, state: &BisectState) -> Result<(), UiError> {
+    util::write_atomic(
+        &state_path(app, state.installation_id)?,
+        &serde_json::to_vec_pretty(state)?,
+    )
+}
+
+fn active<R: Runtime>(app: &AppHandle<R>, installation_id: u64) -> Result<BisectState, UiError> {
+    load(app, installation_id)?
+        .filter(|state| state.status == BisectStatus::Testing)
+        .ok_or_else(|| {
+            UiError::new(
+                ErrorCode::InvalidState,
+                "No bisection is running for this installation",
+            )
+        })
+}
+
+fn ensure_not_running<R: Runtime>(app: &AppHandle<R>, installation_id: u64) -> Result<(), UiError> {
+    if processes::is_running(app, installation_id) {
+        return Err(UiError::new(
+            ErrorCode::InvalidState,
+            "Close the game before changing its mods",
+        ));
+    }
+    Ok(())
+}
+
+// A suspect plus every mod it needs, the game refuses to load it otherwise
+fn closure(state: &BisectState, filename: &str) -> HashSet<String> {
+    let mut by_modid: HashMap<String, Vec<&str>> = HashMap::new();
+    for m in &state.mods {
+        by_modid
+            .entry(m.modid.to_lowercase())
+            .or_default()
+            .push(&m.filename);
+    }
+    let mut found = HashSet::new();
+    let mut queue = vec![filename.to_string()];
+    while let Some(next) = queue.pop() {
+        if !found.insert(next.clone()) {
+            continue;
+        }
+        let Some(m) = state.mods.iter().find(|m| m.filename == next) else {
+            continue;
+        };
+        for dependency in &m.dependencies {
+            for filename in by_modid.get(dependency).into_iter().flatten() {
+                queue.push(filename.to_string());
+            }
+        }
+    }
+    found
+}
+
+// Picks about half of the suspects, together with their dependencies. None when the
+// suspects can't be split, e.g. because they all depend on each other.
+fn next_round(state: &BisectState) -> Option<Vec<String>> {
+    let mut candidates: Vec<(String, HashSet<String>)> = state
+        .suspects
+        .iter()
+        .map(|s| (s.clone(), closure(state, s)))
+        .collect();
+    candidates.sort_by_key(|(_, needs)| needs.len());
+
+    let target = state.suspects.len().div_ceil(2);
+    let mut enabled: HashSet<String> = HashSet::new();
+    for (_, needs) in candidates {
+        if state
+            .suspects
+            .iter()
+            .filter(|s| enabled.contains(*s))
+            .count()
+            >= target
+        {
+            break;
+        }
+        enabled.extend(needs);
+    }
+    if state.suspects.iter().all(|s| enabled.contains(s)) {
+        return None;
+    }
+    // Keep the original order, it is what the user sees
+    Some(
+        state
+            .mods
+            .iter()
+            .map(|m| m.filename.clone())
+            .filter(|f| enabled.contains(f))
+            .collect(),
+    )
+}
+
+// Moves the tracked mods so exactly `enabled` of them are in Mods/
+fn apply(installation_path: &Path, state: &BisectState, enabled: &[String]) -> Result<(), UiError> {
+    for m in &state.mods {
+        let current = [
+            installation_path.join("Mods"),
+            installation_path.join(DISABLED_DIR),
+        ]
+        .into_iter()
+        .map(|dir| dir.join(&m.filename))
+        .find(|path| path.exists())
+        .ok_or_else(|| UiError::not_found(format!("{} was removed", m.filename)))?;
+        mods::set_mod_enabled(installation_path, &current, enabled.contains(&m.filename))?;
+    }
+    Ok(())
+}
+
+// Ends a finished bisection with everything but the culprits enabled again
+fn conclude(
+    installation_path: &Path,
+    state: &mut BisectState,
+    status: BisectStatus,
+) -> Result<(), UiError> {
+    state.status = status;
+    state.culprits = if status == BisectStatus::Found {
+        state.suspects.clone()
+    } else {
+        vec![]
+    };
+    state.testing = vec![];
+    let enabled: Vec<String> = state
+        .mods
+        .iter()
+        .map(|m| m.filename.clone())
+        .filter(|f| !state.culprits.contains(f))
+        .collect();
+    apply(installation_path, state, &enabled)
+}
+
+// Called by the process watcher, remembers how a launch during a bisection ended
+pub fn observe_exit<R: Runtime>(app: &AppHandle<R>, exit: &GameExit, crashed: bool) {
+    let Ok(mut state) = active(app, exit.installation_id) else {
+        return;
+    };
+    let outcome = if crashed || !exit.success {
+        BisectOutcome::Crashed
+    } else {
+        BisectOutcome::Worked
+    };
+    state.observed = Some(outcome);
+    if let Err(e) = save(app, &state) {
+        log::warn!("Failed to save bisection state: {e}");
+        return;
+    }
+    app.emit(
+        "bisect://observed",
+        BisectObserved {
+            installation_id: exit.installation_id,
+            outcome,
+        },
+    )
+    .ok();
+}
+
+#[command]
+pub fn get_bisect(app: AppHandle, installation_id: u64) -> Result<Option<BisectState>, UiError> {
+    load(&app, installation_id)
+}
+
+// Starts bisecting the enabled mods of an installation and sets up the first launch
+#[command]
+pub fn start_bisect(app: AppHandle, installation_id: u64) -> Result<BisectState, UiError> {
+    if load(&app, installation_id)?.is_some_and(|s| s.status == BisectStatus::Testing) {
+        return Err(UiError::new(
+            ErrorCode::InvalidState,
+            "A bisection is already running for this installation",
+        ));
+    }
+    ensure_not_running(&app, installation_id)?;
+    let installation = store::find(&app, installation_id)?;
+    let installed = mods::enabled_mods(installation.path.clone())?;
+    if installed.len() < 2 {
+        return Err(UiError::new(
+            ErrorCode::InvalidState,
+            "Bisecting needs at least two enabled mods",
+        ));
+    }
+    // The rounds run with half the mods, worlds could lose their content
+    backups::create_backup(&app, installation_id, "bisect")?;
+
+    let tracked: Vec<BisectMod> = installed
+        .into_iter()
+        .map(|m| BisectMod {
+            filename: Path::new(&m.path)
+                .file_name()
+                .and_then(|s| s.to_str())
+                .unwrap_or_default()
+                .to_string(),
+            dependencies: m
+                .dependencies
+                .into_keys()
+                .filter(|d| !BUILTIN_MODS.contains(&d.as_str()))
+                .collect(),
+            modid: m.modid,
+            name: m.name,
+        })
+        .collect();
+    let mut state = BisectState {
+        installation_id,
+        started_at: now_millis(),
+        status: BisectStatus::Testing,
+        suspects: tracked.iter().map(|m| m.filename.clone()).collect(),
+        mods: tracked,
+        testing: vec![],
+        rounds: vec![],
+        observed: None,
+        culprits: vec![],
+    };
+    let installation_path = PathBuf::from(&installation.path);
+    match next_round(&state) {
+        Some(round) => {
+            apply(&installation_path, &state, &round)?;
+            state.testing = round;
+        }
+        None => conclude(&installation_path, &mut state, BisectStatus::Found)?,
+    }
+    save(&app, &state)?;
+    Ok(state)
+}
+
+// Launches the game with the current round's mods
+#[command]
+pub async fn launch_bisect(app: AppHandle, installation_id: u64) -> Result<String, UiError> {
+    active(&app, installation_id)?;
+    installations::play_game(
+        app,
+        Some(PlayGameParams {
+            installation_id,
+            server: None,
+            password: None,
+            backup_saves: Some(false),
+        }),
+    )
+    .await
+}
+
+// Records whether the current round worked and moves on to the next one
+#[command]
+pub fn answer_bisect(
+    app: AppHandle,
+    installation_id: u64,
+    outcome: BisectOutcome,
+) -> Result<BisectState, UiError> {
+    let mut state = active(&app, installation_id)?;
+    ensure_not_running(&app, installation_id)?;
+    let installation = store::find(&app, installation_id)?;
+    let installation_path = PathBuf::from(&installation.path);
+
+    let testing = std::mem::take(&mut state.testing);
+    state
+        .suspects
+        .retain(|s| testing.contains(s) == (outcome == BisectOutcome::Crashed));
+    state.rounds.push(BisectRound {
+        enabled: testing,
+        outcome,
+    });
+    state.observed = None;
+
+    match state.suspects.len() {
+        0 => conclude(&installation_path, &mut state, BisectStatus::Inconclusive)?,
+        1 => conclude(&installation_path, &mut state, BisectStatus::Found)?,
+        _ => match next_round(&state) {
+            Some(round) => {
+                apply(&installation_path, &state, &round)?;
+                state.testing = round;
+            }
+            None => conclude(&installation_path, &mut state, BisectStatus::Found)?,
+        },
+    }
+    save(&app, &state)?;
+    Ok(state)
+}
+
+// Aborts a running bisection with all mods back as they were, or forgets a finished one
+#[command]
+pub fn stop_bisect(app: AppHandle, installation_id: u64) -> Result<(), UiError> {
+    let Some(state) = load(&app, installation_id)? else {
+        return Ok(());
+    };
+    if state.status == BisectStatus::Testing {
+        ensure_not_running(&app, installation_id)?;
+        let installation = store::find(&app, installation_id)?;
+        let enabled: Vec<String> = state.mods.iter().map(|m| m.filename.clone()).collect();
+        apply(Path::new(&installation.path), &state, &enabled)?;
+    }
+    fs::remove_file(state_path(&app, installation_id)?)
+        .map_err(|e| UiError::io("Failed to remove bisection state", e))
+}
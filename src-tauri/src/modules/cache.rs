@@ -0,0 +1,106 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{fs, path::PathBuf, time::Duration};
+use tauri::{command, AppHandle, Manager, Runtime};
+
+use super::errors::UiError;
+use super::util::{self, now_millis};
+
+const CACHE_DIR: &str = "cache/http";
+
+// How long a cached response is served as is, and how much longer it may still be
+// served while a fresh copy is fetched in the background
+#[derive(Debug, Clone, Copy)]
+pub struct CachePolicy {
+    pub ttl: Duration,
+    pub stale: Duration,
+}
+
+impl CachePolicy {
+    pub const fn minutes(ttl: u64, stale: u64) -> Self {
+        CachePolicy {
+            ttl: Duration::from_secs(ttl * 60),
+            stale: Duration::from_secs(stale * 60),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedResponse {
+    pub url: String,
+    // Milliseconds since the epoch, reset whenever the server confirms the copy
+    pub fetched_at: u64,
+    #[serde(default)]
+    pub etag: Option<String>,
+    #[serde(default)]
+    pub last_modified: Option<String>,
+    pub body: String,
+}
+
+impl CachedResponse {
+    pub fn new(
+        url: String,
+        body: String,
+        etag: Option<String>,
+        last_modified: Option<String>,
+    ) -> Self {
+        CachedResponse {
+            url,
+            fetched_at: now_millis(),
+            etag,
+            last_modified,
+            body,
+        }
+    }
+
+    pub fn age(&self) -> Duration {
+        Duration::from_millis(now_millis().saturating_sub(self.fetched_at))
+    }
+}
+
+fn cache_dir<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, UiError> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(CACHE_DIR))
+        .map_err(|e| UiError::io("Failed to resolve app data dir", e))
+}
+
+fn entry_path<R: Runtime>(app: &AppHandle<R>, url: &str) -> Result<PathBuf, UiError> {
+    let name: String = Sha256::digest(url.as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    Ok(cache_dir(app)?.join(format!("{name}.json")))
+}
+
+pub fn load<R: Runtime>(app: &AppHandle<R>, url: &str) -> Option<CachedResponse> {
+    let text = fs::read_to_string(entry_path(app, url).ok()?).ok()?;
+    serde_json::from_str::<CachedResponse>(&text)
+        .ok()
+        .filter(|entry| entry.url == url)
+}
+
+// Failing to cache never fails the request that produced the body
+pub fn store<R: Runtime>(app: &AppHandle<R>, entry: &CachedResponse) {
+    let result = entry_path(app, &entry.url)
+        .and_then(|path| util::write_atomic(&path, &serde_json::to_vec(entry)?));
+    if let Err(e) = result {
+        log::warn!("Failed to cache response of {}: {e}", entry.url);
+    }
+}
+
+// Removes every cached response and returns how many there were
+#[command]
+pub fn clear_http_cache(app: AppHandle) -> Result<usize, UiError> {
+    let dir = cache_dir(&app)?;
+    if !dir.is_dir() {
+        return Ok(0);
+    }
+    let count = fs::read_dir(&dir)
+        .map_err(|e| UiError::io("Failed to read cache directory", e))?
+        .flatten()
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+        .count();
+    fs::remove_dir_all(&dir).map_err(|e| UiError::io("Failed to clear cache", e))?;
+    Ok(count)
+}
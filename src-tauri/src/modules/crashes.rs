@@ -0,0 +1,276 @@
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+use tauri::{command, AppHandle};
+
+use super::errors::UiError;
+use super::mods::{self, OutputMod};
+use super::store;
+use super::util;
+
+const LOG_FILES: [&str; 2] = ["client-crash.txt", "client-main.txt"];
+
+// Namespaces of the game, the runtime and common libraries, frames in these never point at a mod
+const IGNORED_NAMESPACES: [&str; 12] = [
+    "vintagestory",
+    "system",
+    "microsoft",
+    "harmonylib",
+    "monomod",
+    "protobuf",
+    "newtonsoft",
+    "cairo",
+    "opentk",
+    "sentry",
+    "skiasharp",
+    "dmd",
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CrashException {
+    pub file: String,
+    pub kind: String,
+    pub message: String,
+    pub frames: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SuspectedMod {
+    pub modid: String,
+    pub name: String,
+    pub version: String,
+    pub path: String,
+    // Higher means more evidence, the list is sorted by it
+    pub score: u32,
+    pub reasons: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CrashReport {
+    pub game_version: Option<String>,
+    // As written by the game, e.g. "20.3.2024 14:32:11"
+    pub timestamp: Option<String>,
+    // Modification time of client-crash.txt in milliseconds since the epoch
+    pub crashed_at: Option<u64>,
+    pub exceptions: Vec<CrashException>,
+    pub suspected_mods: Vec<SuspectedMod>,
+    pub log_files: Vec<String>,
+}
+
+// Maps lowercased identifiers (mod ids and names without spaces) to installed mods
+struct ModIndex<'a> {
+    keys: HashMap<String, &'a OutputMod>,
+}
+
+impl<'a> ModIndex<'a> {
+    fn new(mods: &'a [OutputMod]) -> Self {
+        let mut keys = HashMap::new();
+        for m in mods {
+            keys.insert(normalize(&m.modid), m);
+            keys.insert(normalize(&m.name), m);
+        }
+        keys.remove("");
+        ModIndex { keys }
+    }
+
+    fn get(&self, ident: &str) -> Option<&'a OutputMod> {
+        self.keys.get(&normalize(ident)).copied()
+    }
+
+    // Looks for any known mod identifier among the words of a line
+    fn find_in(&self, line: &str) -> Vec<&'a OutputMod> {
+        let mut found: Vec<&OutputMod> = vec![];
+        for word in line.split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-')) {
+            if let Some(m) = self.get(word) {
+                if !found.iter().any(|f| f.modid == m.modid) {
+                    found.push(m);
+                }
+            }
+        }
+        found
+    }
+}
+
+fn normalize(s: &str) -> String {
+    s.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn add_suspect(suspects: &mut Vec<SuspectedMod>, m: &OutputMod, score: u32, reason: String) {
+    let suspect = match suspects.iter_mut().find(|s| s.modid == m.modid) {
+        Some(s) => s,
+        None => {
+            suspects.push(SuspectedMod {
+                modid: m.modid.clone(),
+                name: m.name.clone(),
+                version: m.version.clone(),
+                path: m.path.clone(),
+                score: 0,
+                reasons: vec![],
+            });
+            suspects.last_mut().unwrap()
+        }
+    };
+    suspect.score += score;
+    if !suspect.reasons.contains(&reason) {
+        suspect.reasons.push(reason);
+    }
+}
+
+// "System.NullReferenceException: Object reference not set" -> ("System.NullReferenceException", "Object ...")
+fn parse_exception_line(line: &str) -> Option<(String, String)> {
+    let (kind, message) = match line.split_once(": ") {
+        Some((kind, message)) => (kind, message),
+        None => (line, ""),
+    };
+    let kind = kind.trim();
+    let is_exception = kind.ends_with("Exception") || kind.ends_with("Error");
+    if !is_exception || !kind.contains('.') || kind.contains(' ') {
+        return None;
+    }
+    Some((kind.to_string(), message.trim().to_string()))
+}
+
+// "at FooMod.Systems.Bar.Start(ICoreAPI api) in ..." -> "FooMod"
+fn frame_namespace(frame: &str) -> Option<&str> {
+    let target = frame.strip_prefix("at ")?.trim_start();
+    let ns = target.split(['.', '(', ' ', '`']).next()?;
+    // Harmony patches show up as "DMD<...>" or "(wrapper dynamic-method)"
+    let ns = ns.trim_start_matches("DMD<");
+    if ns.is_empty() || IGNORED_NAMESPACES.contains(&ns.to_lowercase().as_str()) {
+        return None;
+    }
+    Some(ns)
+}
+
+// "Critical error occurred in the following mod: foomod@1.2.3" -> "foomod"
+fn crashed_mod_id(line: &str) -> Option<&str> {
+    let (_, rest) = line.split_once("in the following mod:")?;
+    rest.split_whitespace()
+        .next()
+        .map(|id| id.split('@').next().unwrap_or(id))
+}
+
+fn analyze_file(
+    file: &str,
+    contents: &str,
+    index: &ModIndex,
+    report: &mut CrashReport,
+    suspects: &mut Vec<SuspectedMod>,
+) {
+    let mut current: Option<CrashException> = None;
+    for raw in contents.lines() {
+        let line = raw.trim();
+
+        if line.starts_with("at ") {
+            if let Some(exception) = current.as_mut() {
+                exception.frames.push(line.to_string());
+                if let Some(m) = frame_namespace(line).and_then(|ns| index.get(ns)) {
+                    add_suspect(suspects, m, 3, format!("Stack frame in {file}: {line}"));
+                }
+                continue;
+            }
+        } else if let Some(exception) = current.take() {
+            report.exceptions.push(exception);
+        }
+
+        if let Some(version) = line.strip_prefix("Game Version:") {
+            report.game_version.get_or_insert_with(|| {
+                version
+                    .split_whitespace()
+                    .next()
+                    .unwrap_or("")
+                    .trim_start_matches('v')
+                    .to_string()
+            });
+        }
+        if let Some((timestamp, _)) = line.split_once(": Critical error occurred") {
+            report
+                .timestamp
+                .get_or_insert_with(|| timestamp.trim().to_string());
+        }
+        if let Some(m) = crashed_mod_id(line).and_then(|id| index.get(id)) {
+            add_suspect(suspects, m, 10, format!("Named by the game in {file}"));
+        }
+
+        let lower = line.to_lowercase();
+        if lower.contains("threw") && lower.contains("exception") {
+            for m in index.find_in(line) {
+                add_suspect(suspects, m, 5, format!("{file}: {line}"));
+            }
+        }
+
+        // Exceptions may be prefixed by a timestamp and log level in client-main.txt
+        let candidate = line.rsplit("] ").next().unwrap_or(line);
+        if let Some((kind, message)) = parse_exception_line(candidate) {
+            current = Some(CrashException {
+                file: file.to_string(),
+                kind,
+                message,
+                frames: vec![],
+            });
+        }
+    }
+    if let Some(exception) = current {
+        report.exceptions.push(exception);
+    }
+}
+
+fn modified_millis(path: &Path) -> Option<u64> {
+    fs::metadata(path).ok()?.modified().ok().map(util::millis)
+}
+
+// Returns None when the installation has no crash log to look at
+pub fn analyze(data_path: &Path) -> Result<Option<CrashReport>, UiError> {
+    let logs_path = data_path.join("Logs");
+    let crash_path = logs_path.join(LOG_FILES[0]);
+    if !crash_path.exists() {
+        return Ok(None);
+    }
+    // A missing Mods folder just means nothing can be blamed
+    let installed =
+        mods::enabled_mods(data_path.to_string_lossy().into_owned()).unwrap_or_default();
+    let index = ModIndex::new(&installed);
+
+    let mut report = CrashReport {
+        game_version: None,
+        timestamp: None,
+        crashed_at: modified_millis(&crash_path),
+        exceptions: vec![],
+        suspected_mods: vec![],
+        log_files: vec![],
+    };
+    let mut suspects = vec![];
+    for file in LOG_FILES {
+        let path: PathBuf = logs_path.join(file);
+        let Ok(bytes) = fs::read(&path) else {
+            continue;
+        };
+        analyze_file(
+            file,
+            &String::from_utf8_lossy(&bytes),
+            &index,
+            &mut report,
+            &mut suspects,
+        );
+        report.log_files.push(path.to_string_lossy().into_owned());
+    }
+    suspects.sort_by_key(|s| std::cmp::Reverse(s.score));
+    report.suspected_mods = suspects;
+    Ok(Some(report))
+}
+
+#[command]
+pub fn analyze_crash(app: AppHandle, installation_id: u64) -> Result<CrashReport, UiError> {
+    let installation = store::find(&app, installation_id)?;
+    analyze(Path::new(&installation.path))?.ok_or_else(|| {
+        UiError::not_found(format!(
+            "No crash log found for installation {installation_id}"
+        ))
+    })
+}
@@ -0,0 +1,223 @@
+use serde::Serialize;
+use std::{cmp::Ordering, collections::HashMap};
+use tauri::{command, AppHandle};
+
+use super::errors::UiError;
+use super::mods::{self, ModInfo, ModRelease, OutputMod};
+use super::store;
+
+// Dependencies that ship with the game and follow its version
+pub const BUILTIN_MODS: [&str; 3] = ["game", "survival", "creative"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IssueKind {
+    // Not installed at all
+    Missing,
+    // Installed, but older than required
+    Unsatisfied,
+    // The same modid is installed more than once
+    Conflict,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DependencyIssue {
+    pub kind: IssueKind,
+    // The mod that declares the dependency, or the duplicated mod for conflicts
+    pub modid: String,
+    pub dependency: String,
+    pub required: String,
+    pub installed: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProposedRelease {
+    pub modid: String,
+    pub version: String,
+    pub releaseid: Option<i64>,
+    pub filename: Option<String>,
+    pub url: String,
+    // Whether the release is tagged for the installation's game version
+    pub matches_game_version: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DependencyReport {
+    pub issues: Vec<DependencyIssue>,
+    pub proposals: Vec<ProposedRelease>,
+    // Dependencies no ModDB release could be found for
+    pub unresolved: Vec<String>,
+}
+
+// Compares "1.2.3", "v1.19.0-rc.2" and the like; a pre-release sorts before its release
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    fn split(v: &str) -> (Vec<u64>, Option<&str>) {
+        let v = v.trim().trim_start_matches(['v', 'V']);
+        let (core, pre) = match v.split_once('-') {
+            Some((core, pre)) => (core, Some(pre)),
+            None => (v, None),
+        };
+        let nums = core
+            .split('.')
+            .map(|p| {
+                p.chars()
+                    .take_while(|c| c.is_ascii_digit())
+                    .collect::<String>()
+                    .parse()
+                    .unwrap_or(0)
+            })
+            .collect();
+        (nums, pre)
+    }
+    let (an, ap) = split(a);
+    let (bn, bp) = split(b);
+    for i in 0..an.len().max(bn.len()) {
+        let x = an.get(i).copied().unwrap_or(0);
+        let y = bn.get(i).copied().unwrap_or(0);
+        if x != y {
+            return x.cmp(&y);
+        }
+    }
+    match (ap, bp) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(x), Some(y)) => compare_versions(
+            &x.replace(|c: char| !c.is_ascii_digit() && c != '.', ""),
+            &y.replace(|c: char| !c.is_ascii_digit() && c != '.', ""),
+        ),
+    }
+}
+
+// Dependency versions are minimums, empty or "*" accepts anything
+pub fn satisfies(installed: &str, required: &str) -> bool {
+    let required = required.trim();
+    required.is_empty()
+        || required == "*"
+        || compare_versions(installed, required) != Ordering::Less
+}
+
+pub fn check(installed: &[OutputMod], game_version: &str) -> Vec<DependencyIssue> {
+    let mut by_id: HashMap<String, Vec<&OutputMod>> = HashMap::new();
+    for m in installed {
+        by_id.entry(m.modid.to_lowercase()).or_default().push(m);
+    }
+
+    let mut issues = vec![];
+    for m in installed {
+        for (dependency, required) in &m.dependencies {
+            let versions: Vec<String> = if BUILTIN_MODS.contains(&dependency.as_str()) {
+                vec![game_version.to_string()]
+            } else {
+                by_id
+                    .get(dependency)
+                    .map(|found| found.iter().map(|f| f.version.clone()).collect())
+                    .unwrap_or_default()
+            };
+            let kind = if versions.is_empty() {
+                IssueKind::Missing
+            } else if !versions.iter().any(|v| satisfies(v, required)) {
+                IssueKind::Unsatisfied
+            } else {
+                continue;
+            };
+            issues.push(DependencyIssue {
+                kind,
+                modid: m.modid.clone(),
+                dependency: dependency.clone(),
+                required: required.clone(),
+                installed: versions,
+            });
+        }
+    }
+
+    let mut duplicates: Vec<_> = by_id.iter().filter(|(_, found)| found.len() > 1).collect();
+    duplicates.sort_by_key(|(id, _)| id.as_str());
+    for (modid, found) in duplicates {
+        issues.push(DependencyIssue {
+            kind: IssueKind::Conflict,
+            modid: modid.clone(),
+            dependency: modid.clone(),
+            required: String::new(),
+            installed: found.iter().map(|f| f.version.clone()).collect(),
+        });
+    }
+    issues
+}
+
+// The best release for the game version among those that satisfy the requirement
+fn pick_release(
+    info: &ModInfo,
+    modid: &str,
+    required: &str,
+    game_version: &str,
+) -> Option<ProposedRelease> {
+    let candidates: Vec<ModRelease> = info
+        .details
+        .releases
+        .iter()
+        .filter(|r| satisfies(&r.modversion, required))
+        .cloned()
+        .collect();
+    let release = mods::best_release(&candidates, game_version)?;
+    Some(ProposedRelease {
+        modid: modid.to_string(),
+        version: release.modversion.clone(),
+        releaseid: Some(release.releaseid),
+        filename: Some(release.filename.clone()),
+        url: release.mainfile.clone(),
+        matches_game_version: release.tagged_for(game_version),
+    })
+}
+
+#[command]
+pub async fn resolve_dependencies(
+    app: AppHandle,
+    installation_id: u64,
+    propose: Option<bool>,
+) -> Result<DependencyReport, UiError> {
+    let installation = store::find(&app, installation_id)?;
+    let installed = mods::enabled_mods(installation.path)?;
+    let issues = check(&installed, &installation.version);
+
+    let mut proposals: Vec<ProposedRelease> = vec![];
+    let mut unresolved = vec![];
+    if propose.unwrap_or(true) {
+        // The strictest requirement per dependency, the game itself can't be installed from ModDB
+        let mut wanted: Vec<(String, String)> = vec![];
+        for issue in &issues {
+            if issue.kind == IssueKind::Conflict
+                || BUILTIN_MODS.contains(&issue.dependency.as_str())
+            {
+                continue;
+            }
+            match wanted.iter_mut().find(|(id, _)| *id == issue.dependency) {
+                Some((_, required)) => {
+                    if compare_versions(&issue.required, required) == Ordering::Greater {
+                        *required = issue.required.clone();
+                    }
+                }
+                None => wanted.push((issue.dependency.clone(), issue.required.clone())),
+            }
+        }
+        for (modid, required) in wanted {
+            let release = match mods::fetch_mod_info(app.clone(), modid.clone()).await {
+                Ok(info) => pick_release(&info, &modid, &required, &installation.version),
+                Err(e) => {
+                    log::warn!("Failed to look up {modid} on ModDB: {e}");
+                    None
+                }
+            };
+            match release {
+                Some(release) => proposals.push(release),
+                None => unresolved.push(modid),
+            }
+        }
+    }
+
+    Ok(DependencyReport {
+        issues,
+        proposals,
+        unresolved,
+    })
+}
@@ -1,21 +1,29 @@
+use flate2::read::GzDecoder;
 use futures_util::StreamExt;
-use reqwest::get;
-use reqwest::header::CONTENT_DISPOSITION;
-use serde::Serialize;
+use md5::Md5;
+use reqwest::header::{CONTENT_DISPOSITION, CONTENT_RANGE, RANGE};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use sha1::Sha1;
+use sha2::{digest::DynDigest, Digest, Sha256};
 use std::{
-    fs::{self, File},
-    io::{self, Seek, Write},
-    path::{Path, PathBuf},
-    process::Command,
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Seek, Write},
+    path::{Component, Path, PathBuf},
+    time::Duration,
 };
-use tauri::{command, Emitter, Runtime};
+use tauri::{command, AppHandle, Emitter, Runtime};
+use tokio::sync::watch;
 
-use super::errors::UiError;
+use super::errors::{ErrorCode, UiError};
+use super::http::{self, Service};
+use super::jobs::{JobControl, JobSignal};
 
 #[derive(Serialize, Clone)]
 pub struct ProgressPayload {
-    phase: &'static str, // "download" | "extract"
+    // "download" | "resume" | "verify" | "extract" | "import" | "clone", or a job state
+    phase: &'static str,
     downloaded: Option<u64>,
     total: Option<u64>,
     percent: Option<f64>,
@@ -24,7 +32,52 @@ pub struct ProgressPayload {
     message: Option<String>,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct DownloadRequest {
+    pub url: String,
+    pub destpath: String,
+    pub emitevent: String,
+    pub extract: bool,
+    pub extractdir: Option<String>,
+    pub zipsubfolderprefix: Option<String>,
+    pub expectedsize: Option<u64>,
+    pub expectedhash: Option<String>,
+    pub stripcomponents: Option<u32>,
+    // Used when the server doesn't name the file in Content-Disposition
+    #[serde(default)]
+    pub filename: Option<String>,
+}
+
+impl ProgressPayload {
+    // Bare phase notification without any progress numbers
+    pub fn phase(phase: &'static str, message: Option<String>) -> Self {
+        ProgressPayload {
+            phase,
+            downloaded: None,
+            total: None,
+            percent: None,
+            current: None,
+            count: None,
+            message,
+        }
+    }
+
+    // Item `current` of `count` in a multi step operation
+    pub fn step(phase: &'static str, current: u64, count: u64, message: String) -> Self {
+        ProgressPayload {
+            phase,
+            downloaded: None,
+            total: None,
+            percent: (count > 0).then(|| (current as f64 / count as f64) * 100.0),
+            current: Some(current),
+            count: Some(count),
+            message: Some(message),
+        }
+    }
+}
+
 #[command]
+#[allow(clippy::too_many_arguments)]
 pub async fn download_and_maybe_extract<R: Runtime>(
     app: tauri::AppHandle<R>,
     url: String,
@@ -37,77 +90,156 @@ pub async fn download_and_maybe_extract<R: Runtime>(
     // only extract entries whose path starts with this folder inside the zip, e.g. "docs/".
     // Use "" or None to extract all
     zipsubfolderprefix: Option<String>,
+    // verification controls, usually taken from the download index
+    expectedsize: Option<u64>,
+    // "md5:<hex>", "sha1:<hex>", "sha256:<hex>" or a bare hex digest
+    expectedhash: Option<String>,
+    // leading path components dropped from .tar.gz entries, defaults to 1
+    stripcomponents: Option<u32>,
+) -> Result<String, UiError> {
+    let request = DownloadRequest {
+        url,
+        destpath,
+        emitevent,
+        extract,
+        extractdir,
+        zipsubfolderprefix,
+        expectedsize,
+        expectedhash,
+        stripcomponents,
+        filename: None,
+    };
+    run(&app, request, None).await
+}
+
+// Runs a download to completion. Inside a download job the transfer stops as soon
+// as the job is paused or cancelled; paused jobs keep their .part file.
+pub async fn run<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+    request: DownloadRequest,
+    job: Option<&JobControl>,
 ) -> Result<String, UiError> {
-    // 1) Download
-    let resp = get(&url).await.map_err(|e| format!("request error: {e}"))?;
+    download(app, request, job).await.map(|_| "success".into())
+}
 
-    // Get the filename from Content-Disposition or fallback
-    // Content-Disposition: attachment; filename="example.pdf"
-    let filename = resp
-        .headers()
-        .get(CONTENT_DISPOSITION)
-        .and_then(|cd| cd.to_str().ok())
-        .and_then(|cd_str| {
-            cd_str.split(';').find_map(|part| {
-                let part = part.trim();
-                if part.starts_with("filename=") {
-                    Some(part.trim_start_matches("filename=").trim_matches('"'))
-                } else {
-                    None
+// Like `run`, but returns the path of the downloaded file
+pub async fn download<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+    request: DownloadRequest,
+    job: Option<&JobControl>,
+) -> Result<PathBuf, UiError> {
+    let DownloadRequest {
+        url,
+        destpath,
+        emitevent,
+        extract,
+        extractdir,
+        zipsubfolderprefix,
+        expectedsize,
+        expectedhash,
+        stripcomponents,
+        filename: fallback_name,
+    } = request;
+    let signal = job.map(|j| j.signal());
+    let api = http::api(app);
+    let client = api.client();
+
+    // 1) Resolve the target filename. The first response tells us the name,
+    // which in turn tells us whether a partial download is already on disk.
+    let mut attempt: u32 = 0;
+    let resp = loop {
+        let result = tokio::select! {
+            resp = client.get(&url).send() => match resp {
+                Ok(resp) if resp.status().is_success() => Ok(resp),
+                Ok(resp) => Err(status_error(resp.status())),
+                Err(e) => Err(FetchError::Transient(e.into())),
+            },
+            _ = interrupted(signal) => Err(FetchError::Interrupted),
+        };
+        match result {
+            Ok(resp) => break resp,
+            Err(FetchError::Transient(e)) if attempt < MAX_RETRIES => {
+                attempt += 1;
+                if !backoff(app, &emitevent, attempt, &e, None, expectedsize, signal).await {
+                    return Err(interruption(signal, None));
                 }
-            })
-        })
-        .unwrap_or_else(|| {
-            url.split('/')
-                .last()
-                .unwrap_or(if cfg!(target_os = "windows") {
-                    "downloaded_file.zip"
-                } else {
-                    "downloaded_file.tar.gz"
-                })
-        });
+            }
+            Err(FetchError::Interrupted) => return Err(interruption(signal, None)),
+            Err(FetchError::Transient(e)) | Err(FetchError::Fatal(e)) => return Err(e),
+        }
+    };
 
-    let filepathbuf = PathBuf::from(&destpath).join(filename);
+    let filename = filename_from_response(&resp, &url, fallback_name.as_deref());
+    let filepathbuf = PathBuf::from(&destpath).join(&filename);
     let filepath = Path::new(&filepathbuf);
-
-    if !resp.status().is_success() {
-        return Err(UiError::from(format!("HTTP error: {}", resp.status())));
+    let partpath = PathBuf::from(&destpath).join(format!("{filename}.part"));
+    if let Some(job) = job {
+        job.track_partial(&partpath);
     }
 
     let destpath = PathBuf::from(&destpath);
     if !destpath.exists() {
-        fs::create_dir_all(&destpath)
-            .map_err(|e| UiError::from(format!("create dir error: {e}")))?;
+        fs::create_dir_all(&destpath).map_err(|e| UiError::io("create dir error", e))?;
     }
 
-    let total = resp.content_length();
-    let mut file =
-        File::create(&filepath).map_err(|e| UiError::from(format!("file create error: {e}")))?;
-
-    let mut stream = resp.bytes_stream();
-    let mut downloaded: u64 = 0;
-
-    while let Some(chunk) = stream.next().await {
-        let chunk = chunk.map_err(|e| format!("stream error: {e}"))?;
-        file.write_all(&chunk)
-            .map_err(|e| format!("file write error: {e}"))?;
-        downloaded += chunk.len() as u64;
+    // 2) Download into the .part file, resuming and retrying as needed
+    let mut first = Some(resp);
+    loop {
+        let result = match first.take() {
+            // Only reuse the first response when there is nothing to resume
+            Some(resp) if part_len(&partpath) == 0 => {
+                stream_to_part(app, resp, &partpath, 0, &emitevent, signal).await
+            }
+            _ => {
+                fetch_range(
+                    app,
+                    client,
+                    &url,
+                    &partpath,
+                    expectedsize,
+                    &emitevent,
+                    signal,
+                )
+                .await
+            }
+        };
+        match result {
+            Ok(()) => break,
+            Err(FetchError::Transient(e)) if attempt < MAX_RETRIES => {
+                attempt += 1;
+                let downloaded = Some(part_len(&partpath));
+                if !backoff(
+                    app,
+                    &emitevent,
+                    attempt,
+                    &e,
+                    downloaded,
+                    expectedsize,
+                    signal,
+                )
+                .await
+                {
+                    return Err(interruption(signal, Some(&partpath)));
+                }
+            }
+            Err(FetchError::Interrupted) => return Err(interruption(signal, Some(&partpath))),
+            Err(FetchError::Transient(e)) | Err(FetchError::Fatal(e)) => return Err(e),
+        }
+    }
 
-        let percent = total.map(|t| (downloaded as f64 / t as f64) * 100.0);
-        app.emit(
-            &emitevent,
-            ProgressPayload {
-                phase: "download",
-                downloaded: Some(downloaded),
-                total,
-                percent,
-                current: None,
-                count: None,
-                message: None,
-            },
-        )
-        .map_err(|e| format!("emit error: {e}"))?;
+    // 3) Verify before the file becomes visible under its real name
+    if let Err(e) = verify_download(
+        app,
+        &partpath,
+        expectedsize,
+        expectedhash.as_deref(),
+        &emitevent,
+    ) {
+        // A corrupt partial file must not be resumed on the next attempt
+        fs::remove_file(&partpath).ok();
+        return Err(e);
     }
+    fs::rename(&partpath, filepath).map_err(|e| UiError::io("rename error", e))?;
 
     // Optionally extract ZIP content
     if extract {
@@ -118,19 +250,24 @@ pub async fn download_and_maybe_extract<R: Runtime>(
             .map(|ext| ext.eq_ignore_ascii_case("zip"))
             .unwrap_or(false)
         {
-            let extract_dir = extractdir
-                .ok_or_else(|| "extract_dir must be provided when extract=true".to_string())?;
+            let extract_dir = extractdir.ok_or_else(|| {
+                UiError::new(
+                    ErrorCode::InvalidParams,
+                    "extract_dir must be provided when extract=true",
+                )
+            })?;
             let extract_dir = PathBuf::from(extract_dir);
             fs::create_dir_all(&extract_dir)
-                .map_err(|e| format!("create extract dir error: {e}"))?;
+                .map_err(|e| UiError::io("create extract dir error", e))?;
 
-            let mut zip_file = File::open(&filepath).map_err(|e| format!("open zip error: {e}"))?;
+            let mut zip_file =
+                File::open(filepath).map_err(|e| UiError::io("open zip error", e))?;
             zip_file
                 .rewind()
-                .map_err(|e| UiError::from(format!("rewind error: {e}")))?;
+                .map_err(|e| UiError::io("rewind error", e))?;
 
-            let mut archive = zip::ZipArchive::new(zip_file)
-                .map_err(|e| UiError::from(format!("zip open error: {e}")))?;
+            let mut archive =
+                zip::ZipArchive::new(zip_file).map_err(|e| UiError::parse("zip open error", e))?;
 
             // Normalize the prefix (folder inside zip)
             let mut prefix = zipsubfolderprefix.unwrap_or_default();
@@ -143,7 +280,7 @@ pub async fn download_and_maybe_extract<R: Runtime>(
             for i in 0..archive.len() {
                 let entry = archive
                     .by_index(i)
-                    .map_err(|e| UiError::from(format!("zip index error: {e}")))?;
+                    .map_err(|e| UiError::parse("zip index error", e))?;
                 let entry_name = entry.name();
                 if should_extract(entry_name, &prefix) {
                     count_to_extract += 1;
@@ -154,35 +291,33 @@ pub async fn download_and_maybe_extract<R: Runtime>(
             let mut processed: u64 = 0;
             // Reopen archive to reset cursor (simplest)
             let mut zip_file =
-                File::open(&filepath).map_err(|e| UiError::from(format!("open zip error: {e}")))?;
+                File::open(filepath).map_err(|e| UiError::io("open zip error", e))?;
             let mut archive = zip::ZipArchive::new(&mut zip_file)
-                .map_err(|e| UiError::from(format!("zip open error: {e}")))?;
+                .map_err(|e| UiError::parse("zip open error", e))?;
 
             for i in 0..archive.len() {
                 let mut entry = archive
                     .by_index(i)
-                    .map_err(|e| UiError::from(format!("zip index error: {e}")))?;
+                    .map_err(|e| UiError::parse("zip index error", e))?;
                 let entry_name = entry.name().to_string();
 
                 if !should_extract(&entry_name, &prefix) {
                     continue;
                 }
 
-                let out_path = make_output_path(&extract_dir, &entry_name, &prefix)
-                    .map_err(|e| UiError::from(format!("path error: {e}")))?;
+                let out_path = make_output_path(&extract_dir, &entry_name, &prefix)?;
 
                 if entry.is_dir() {
-                    fs::create_dir_all(&out_path)
-                        .map_err(|e| UiError::from(format!("mkdir error: {e}")))?;
+                    fs::create_dir_all(&out_path).map_err(|e| UiError::io("mkdir error", e))?;
                 } else {
                     if let Some(parent) = out_path.parent() {
                         fs::create_dir_all(parent)
-                            .map_err(|e| UiError::from(format!("mkdir parent error: {e}")))?;
+                            .map_err(|e| UiError::io("mkdir parent error", e))?;
                     }
-                    let mut out_file = File::create(&out_path)
-                        .map_err(|e| UiError::from(format!("create file error: {e}")))?;
+                    let mut out_file =
+                        File::create(&out_path).map_err(|e| UiError::io("create file error", e))?;
                     io::copy(&mut entry, &mut out_file)
-                        .map_err(|e| UiError::from(format!("extract write error: {e}")))?;
+                        .map_err(|e| UiError::io("extract write error", e))?;
                     // Preserve unix permissions if present
                     #[cfg(unix)]
                     {
@@ -211,16 +346,14 @@ pub async fn download_and_maybe_extract<R: Runtime>(
                         count: Some(count_to_extract),
                         message: Some(format!("Extracted {}", entry_name)),
                     },
-                )
-                .map_err(|e| UiError::from(format!("emit error: {e}")))?;
+                )?;
             }
             // Remove the downloaded archive after extraction
-            fs::remove_file(&filepath)
-                .map_err(|e| UiError::from(format!("remove file error: {e}")))?;
+            fs::remove_file(filepath).map_err(|e| UiError::io("remove file error", e))?;
             // Traverse the destination path and try to find Vintagestory executable
             let mut found_exe = false;
             for entry in walkdir::WalkDir::new(&destpath) {
-                let entry = entry.map_err(|e| UiError::from(format!("walkdir error: {e}")))?;
+                let entry = entry.map_err(|e| UiError::io("walkdir error", e))?;
                 if entry.file_type().is_file() {
                     let fname = entry.file_name().to_string_lossy();
                     if fname.eq_ignore_ascii_case("vintagestory.exe") {
@@ -232,60 +365,138 @@ pub async fn download_and_maybe_extract<R: Runtime>(
             if !found_exe {
                 // Delete the destination path if extraction failed
                 fs::remove_dir_all(&destpath).ok();
-                return Err(UiError::from(
+                return Err(UiError::not_found(
                     "Could not find Vintage Story executable after extraction",
                 ));
             }
         } else {
-            fs::create_dir_all(&destpath)
-                .map_err(|e| UiError::from(format!("create dir error: {e}")))?;
-            let destpath_str = destpath.to_str().unwrap();
-            let mut args = vec![
-                "--strip-components",
-                "1",
-                "-xvf",
-                filepath.to_str().unwrap(),
-                "-C",
-                destpath_str,
-            ];
-            if let Some(sp) = zipsubfolderprefix.as_deref() {
-                if !sp.trim_matches('/').to_string().is_empty() {
-                    args.push(sp);
+            let extract_dir = extractdir
+                .map(PathBuf::from)
+                .unwrap_or_else(|| destpath.clone());
+            fs::create_dir_all(&extract_dir)
+                .map_err(|e| UiError::io("create extract dir error", e))?;
+
+            // Same semantics as `tar --strip-components N -xf archive [member]`
+            let strip = stripcomponents.unwrap_or(1) as usize;
+            let mut prefix = zipsubfolderprefix
+                .unwrap_or_default()
+                .replace('\\', "/")
+                .trim_start_matches("./")
+                .to_string();
+            if !prefix.is_empty() && !prefix.ends_with('/') {
+                prefix.push('/');
+            }
+
+            // First pass: count entries to extract for progress
+            let mut count_to_extract: u64 = 0;
+            let mut archive = open_tar_gz(filepath)?;
+            for entry in archive
+                .entries()
+                .map_err(|e| UiError::parse("tar read error", e))?
+            {
+                let entry = entry.map_err(|e| UiError::parse("tar entry error", e))?;
+                let entry_name = tar_entry_name(&entry)?;
+                if should_extract(&entry_name, &prefix)
+                    && strip_components(&entry_name, strip).is_some()
+                {
+                    count_to_extract += 1;
                 }
             }
 
-            app.emit(
-                &emitevent,
-                ProgressPayload {
-                    phase: "extract",
-                    downloaded: None,
-                    total: None,
-                    percent: None,
-                    current: None,
-                    count: None,
-                    message: None,
-                },
-            )
-            .map_err(|e| UiError::from(format!("emit error: {e}")))?;
-            let status = Command::new(if cfg!(target_os = "macos") {
-                "bsdtar"
-            } else {
-                "tar"
-            })
-            .args(&args)
-            .status()
-            .map_err(|e| UiError::from(format!("tar error: {e}")))?;
-            if !status.success() {
-                return Err(UiError::from("tar failed"));
+            // Second pass: extract
+            let mut processed: u64 = 0;
+            let mut archive = open_tar_gz(filepath)?;
+            archive.set_preserve_permissions(true);
+            for entry in archive
+                .entries()
+                .map_err(|e| UiError::parse("tar read error", e))?
+            {
+                let mut entry = entry.map_err(|e| UiError::parse("tar entry error", e))?;
+                let entry_name = tar_entry_name(&entry)?;
+
+                if !should_extract(&entry_name, &prefix) {
+                    continue;
+                }
+                let Some(stripped) = strip_components(&entry_name, strip) else {
+                    continue;
+                };
+
+                let out_path = make_output_path(&extract_dir, &stripped, "")?;
+                ensure_no_symlinks(&extract_dir, &out_path, &entry_name)?;
+
+                if let Some(parent) = out_path.parent() {
+                    fs::create_dir_all(parent).map_err(|e| UiError::io("mkdir parent error", e))?;
+                }
+
+                let entry_type = entry.header().entry_type();
+                let link_target = if entry_type.is_symlink() || entry_type.is_hard_link() {
+                    let target = entry
+                        .link_name()
+                        .map_err(|e| UiError::parse(format!("tar link error in {entry_name}"), e))?
+                        .map(|t| t.to_string_lossy().replace('\\', "/"))
+                        .ok_or_else(|| {
+                            UiError::new(
+                                ErrorCode::InvalidData,
+                                format!("tar link error in {entry_name}: missing target"),
+                            )
+                        })?;
+                    Some(target)
+                } else {
+                    None
+                };
+
+                match link_target {
+                    // Hard links name another archive member, which is already extracted
+                    Some(target) if entry_type.is_hard_link() => {
+                        let source = hard_link_source(&extract_dir, &target, strip, &entry_name)?;
+                        fs::remove_file(&out_path).ok();
+                        fs::hard_link(&source, &out_path).map_err(|e| {
+                            UiError::io(format!("extract error in {entry_name}"), e)
+                        })?;
+                    }
+                    // Symlinks must not point outside of the extraction directory either
+                    Some(target) => {
+                        check_symlink_target(&extract_dir, &stripped, &target, &entry_name)?;
+                        fs::remove_file(&out_path).ok();
+                        entry.unpack(&out_path).map_err(|e| {
+                            UiError::io(format!("extract error in {entry_name}"), e)
+                        })?;
+                    }
+                    // Preserves unix permissions through set_preserve_permissions
+                    None => {
+                        entry.unpack(&out_path).map_err(|e| {
+                            UiError::io(format!("extract error in {entry_name}"), e)
+                        })?;
+                    }
+                }
+
+                processed += 1;
+                let percent = if count_to_extract > 0 {
+                    Some((processed as f64 / count_to_extract as f64) * 100.0)
+                } else {
+                    None
+                };
+
+                app.emit(
+                    &emitevent,
+                    ProgressPayload {
+                        phase: "extract",
+                        downloaded: None,
+                        total: None,
+                        percent,
+                        current: Some(processed),
+                        count: Some(count_to_extract),
+                        message: Some(format!("Extracted {}", entry_name)),
+                    },
+                )?;
             }
             // Remove the downloaded archive after extraction
-            fs::remove_file(&filepath)
-                .map_err(|e| UiError::from(format!("remove file error: {e}")))?;
+            fs::remove_file(filepath).map_err(|e| UiError::io("remove file error", e))?;
 
             // Traverse the destination path and try to find Vintagestory executable
             let mut found_exe = false;
             for entry in walkdir::WalkDir::new(&destpath) {
-                let entry = entry.map_err(|e| UiError::from(format!("walkdir error: {e}")))?;
+                let entry = entry.map_err(|e| UiError::io("walkdir error", e))?;
                 if entry.file_type().is_file() {
                     let fname = entry.file_name().to_string_lossy();
                     if fname.eq_ignore_ascii_case("vintagestory")
@@ -299,7 +510,7 @@ pub async fn download_and_maybe_extract<R: Runtime>(
             if !found_exe {
                 // Delete the destination path if extraction failed
                 fs::remove_dir_all(&destpath).ok();
-                return Err(UiError::from(
+                return Err(UiError::not_found(
                     "Could not find Vintage Story executable after extraction",
                 ));
             }
@@ -310,77 +521,810 @@ pub async fn download_and_maybe_extract<R: Runtime>(
     app.emit(
         &format!("{emitevent}/done"),
         serde_json::json!({ "path": destpath }),
+    )?;
+
+    Ok(filepathbuf)
+}
+
+const MAX_RETRIES: u32 = 5;
+const RETRY_BASE_DELAY_MS: u64 = 1000;
+
+enum FetchError {
+    // Dropped connections, timeouts and 5xx responses: worth another try
+    Transient(UiError),
+    Fatal(UiError),
+    // The owning job was paused or cancelled
+    Interrupted,
+}
+
+// Resolves once the job leaves the running state, never for plain downloads
+async fn interrupted(signal: Option<&watch::Receiver<JobSignal>>) {
+    match signal {
+        Some(rx) => {
+            let mut rx = rx.clone();
+            let _ = rx.wait_for(|s| *s != JobSignal::Run).await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+// Cancelled downloads drop their .part file, paused ones keep it for resuming
+fn interruption(signal: Option<&watch::Receiver<JobSignal>>, partpath: Option<&Path>) -> UiError {
+    let cancelled = signal
+        .map(|rx| *rx.borrow() == JobSignal::Cancel)
+        .unwrap_or(true);
+    if cancelled {
+        if let Some(partpath) = partpath {
+            fs::remove_file(partpath).ok();
+        }
+        UiError::new(ErrorCode::Cancelled, "Download cancelled")
+    } else {
+        UiError::new(ErrorCode::Paused, "Download paused")
+    }
+}
+
+impl From<tauri::Error> for FetchError {
+    fn from(e: tauri::Error) -> Self {
+        FetchError::Fatal(e.into())
+    }
+}
+
+fn part_len(partpath: &Path) -> u64 {
+    fs::metadata(partpath).map(|m| m.len()).unwrap_or(0)
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+// Keeps only the last path component, the name comes from the server
+fn safe_filename(name: &str) -> Option<String> {
+    let name = name.rsplit(['/', '\\']).next()?.trim();
+    (!name.is_empty() && name != "." && name != "..").then(|| name.to_string())
+}
+
+// Get the filename from Content-Disposition or fallback
+// Content-Disposition: attachment; filename="example.pdf"; filename*=UTF-8''example.pdf
+fn filename_from_response(resp: &reqwest::Response, url: &str, fallback: Option<&str>) -> String {
+    let disposition = resp
+        .headers()
+        .get(CONTENT_DISPOSITION)
+        .and_then(|cd| cd.to_str().ok())
+        .unwrap_or("");
+    let param = |key: &str| {
+        disposition.split(';').find_map(|part| {
+            let (name, value) = part.trim().split_once('=')?;
+            name.trim()
+                .eq_ignore_ascii_case(key)
+                .then(|| value.trim().trim_matches('"'))
+        })
+    };
+    // filename* carries the name in its original encoding and wins when present
+    let extended = param("filename*")
+        .and_then(|v| v.rsplit('\'').next())
+        .map(percent_decode);
+    let from_url = reqwest::Url::parse(url).ok().and_then(|u| {
+        u.path_segments()
+            .and_then(|mut segments| segments.next_back().map(percent_decode))
+    });
+    extended
+        .and_then(|name| safe_filename(&name))
+        .or_else(|| param("filename").and_then(safe_filename))
+        .or_else(|| fallback.and_then(safe_filename))
+        .or_else(|| from_url.and_then(|name| safe_filename(&name)))
+        .unwrap_or_else(|| {
+            if cfg!(target_os = "windows") {
+                "downloaded_file.zip"
+            } else {
+                "downloaded_file.tar.gz"
+            }
+            .to_string()
+        })
+}
+
+// Issues a (possibly ranged) request continuing whatever is already in the .part file
+async fn fetch_range<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+    client: &reqwest::Client,
+    url: &str,
+    partpath: &Path,
+    expectedsize: Option<u64>,
+    emitevent: &str,
+    signal: Option<&watch::Receiver<JobSignal>>,
+) -> Result<(), FetchError> {
+    let existing = part_len(partpath);
+    if existing > 0 && expectedsize == Some(existing) {
+        return Ok(());
+    }
+
+    let mut req = client.get(url);
+    if existing > 0 {
+        req = req.header(RANGE, format!("bytes={existing}-"));
+    }
+    let resp = tokio::select! {
+        resp = req.send() => resp.map_err(|e| FetchError::Transient(e.into()))?,
+        _ = interrupted(signal) => return Err(FetchError::Interrupted),
+    };
+
+    match resp.status() {
+        StatusCode::PARTIAL_CONTENT => {
+            app.emit(
+                emitevent,
+                ProgressPayload {
+                    phase: "resume",
+                    downloaded: Some(existing),
+                    total: content_range_total(&resp).or(expectedsize),
+                    percent: None,
+                    current: None,
+                    count: None,
+                    message: Some(format!("Resuming from byte {existing}")),
+                },
+            )?;
+            stream_to_part(app, resp, partpath, existing, emitevent, signal).await
+        }
+        StatusCode::RANGE_NOT_SATISFIABLE => {
+            // The .part file is at least as large as the remote file; start over
+            fs::remove_file(partpath).ok();
+            Err(FetchError::Transient(UiError::new(
+                ErrorCode::Network,
+                "stale partial download",
+            )))
+        }
+        // The server ignored the Range header and sent the whole file
+        s if s.is_success() => stream_to_part(app, resp, partpath, 0, emitevent, signal).await,
+        s => Err(status_error(s)),
+    }
+}
+
+// Server errors and rate limiting are worth another try, anything else is final
+fn status_error(status: StatusCode) -> FetchError {
+    if status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS {
+        FetchError::Transient(UiError::http_status(status))
+    } else {
+        FetchError::Fatal(UiError::http_status(status))
+    }
+}
+
+// Reports a failed attempt and waits with exponential backoff before the next one.
+// False when the owning job was paused or cancelled meanwhile
+async fn backoff<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+    emitevent: &str,
+    attempt: u32,
+    error: &UiError,
+    downloaded: Option<u64>,
+    total: Option<u64>,
+    signal: Option<&watch::Receiver<JobSignal>>,
+) -> bool {
+    let delay = RETRY_BASE_DELAY_MS * 2u64.pow(attempt - 1);
+    app.emit(
+        emitevent,
+        ProgressPayload {
+            phase: "resume",
+            downloaded,
+            total,
+            percent: None,
+            current: Some(attempt as u64),
+            count: Some(MAX_RETRIES as u64),
+            message: Some(format!("{error}; retrying in {}s", delay / 1000)),
+        },
     )
-    .map_err(|e| format!("emit error: {e}"))?;
+    .ok();
+    tokio::select! {
+        _ = tokio::time::sleep(Duration::from_millis(delay)) => true,
+        _ = interrupted(signal) => false,
+    }
+}
 
-    Ok("success".into())
+// Content-Range: bytes 100-199/200
+fn content_range_total(resp: &reqwest::Response) -> Option<u64> {
+    resp.headers()
+        .get(CONTENT_RANGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.rsplit('/').next())
+        .and_then(|v| v.trim().parse().ok())
 }
 
-fn should_extract(entry_name: &str, prefix: &str) -> bool {
-    if prefix.is_empty() {
-        true
+// Appends the response body to the .part file starting at `offset`
+async fn stream_to_part<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+    resp: reqwest::Response,
+    partpath: &Path,
+    offset: u64,
+    emitevent: &str,
+    signal: Option<&watch::Receiver<JobSignal>>,
+) -> Result<(), FetchError> {
+    let total = if offset > 0 {
+        content_range_total(&resp).or(resp.content_length().map(|l| l + offset))
     } else {
-        // Normalize to forward slashes
-        let n = entry_name.replace('\\', "/");
-        n.starts_with(prefix)
+        resp.content_length()
+    };
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(offset > 0)
+        .truncate(offset == 0)
+        .open(partpath)
+        .map_err(|e| FetchError::Fatal(UiError::io("file create error", e)))?;
+
+    let mut stream = resp.bytes_stream();
+    let mut downloaded: u64 = offset;
+
+    loop {
+        let chunk = tokio::select! {
+            chunk = stream.next() => chunk,
+            _ = interrupted(signal) => return Err(FetchError::Interrupted),
+        };
+        let Some(chunk) = chunk else {
+            break;
+        };
+        let chunk = chunk.map_err(|e| {
+            FetchError::Transient(UiError::wrap(ErrorCode::Network, "stream error", e))
+        })?;
+        file.write_all(&chunk)
+            .map_err(|e| FetchError::Fatal(UiError::io("file write error", e)))?;
+        downloaded += chunk.len() as u64;
+
+        let percent = total.map(|t| (downloaded as f64 / t as f64) * 100.0);
+        app.emit(
+            emitevent,
+            ProgressPayload {
+                phase: "download",
+                downloaded: Some(downloaded),
+                total,
+                percent,
+                current: None,
+                count: None,
+                message: None,
+            },
+        )?;
+    }
+
+    file.flush()
+        .map_err(|e| FetchError::Fatal(UiError::io("file write error", e)))?;
+    if let Some(t) = total {
+        if downloaded < t {
+            return Err(FetchError::Transient(UiError::new(
+                ErrorCode::Network,
+                format!("connection closed after {downloaded} of {t} bytes"),
+            )));
+        }
+    }
+    Ok(())
+}
+
+// Checks size and digest of the finished .part file
+fn verify_download<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+    partpath: &Path,
+    expectedsize: Option<u64>,
+    expectedhash: Option<&str>,
+    emitevent: &str,
+) -> Result<(), UiError> {
+    let size = part_len(partpath);
+    if let Some(expected) = expectedsize {
+        if size != expected {
+            return Err(UiError::new(
+                ErrorCode::Verification,
+                format!("size mismatch: expected {expected} bytes, got {size}"),
+            )
+            .with_details(serde_json::json!({ "expected": expected, "actual": size })));
+        }
+    }
+
+    let Some(expectedhash) = expectedhash.filter(|h| !h.trim().is_empty()) else {
+        return Ok(());
+    };
+    let (algo, expected) = match expectedhash.split_once(':') {
+        Some((algo, hex)) => (algo.to_ascii_lowercase(), hex.trim().to_ascii_lowercase()),
+        // Bare digests are told apart by their length
+        None => {
+            let hex = expectedhash.trim().to_ascii_lowercase();
+            let algo = match hex.len() {
+                32 => "md5",
+                40 => "sha1",
+                _ => "sha256",
+            };
+            (algo.to_string(), hex)
+        }
+    };
+    let mut hasher: Box<dyn DynDigest> = match algo.as_str() {
+        "md5" => Box::new(Md5::new()),
+        "sha1" => Box::new(Sha1::new()),
+        "sha256" => Box::new(Sha256::new()),
+        other => {
+            return Err(UiError::new(
+                ErrorCode::InvalidParams,
+                format!("unsupported hash algorithm: {other}"),
+            ))
+        }
+    };
+
+    let mut file = File::open(partpath).map_err(|e| UiError::io("open file error", e))?;
+    let mut buf = vec![0u8; 1024 * 1024];
+    let mut hashed: u64 = 0;
+    loop {
+        let n = file
+            .read(&mut buf)
+            .map_err(|e| UiError::io("read file error", e))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        hashed += n as u64;
+        app.emit(
+            emitevent,
+            ProgressPayload {
+                phase: "verify",
+                downloaded: Some(hashed),
+                total: Some(size),
+                percent: (size > 0).then(|| (hashed as f64 / size as f64) * 100.0),
+                current: None,
+                count: None,
+                message: None,
+            },
+        )?;
+    }
+
+    let actual: String = hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    if actual != expected {
+        return Err(UiError::new(
+            ErrorCode::Verification,
+            format!("{algo} mismatch: expected {expected}, got {actual}"),
+        )
+        .with_details(serde_json::json!({ "expected": expected, "actual": actual })));
+    }
+    Ok(())
+}
+
+// Matches `pattern` against a single path component, "*" and "?" as in shell globs
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let (pattern, text): (Vec<char>, Vec<char>) =
+        (pattern.chars().collect(), text.chars().collect());
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((bp, bt)) => {
+                    p = bp + 1;
+                    t = bt + 1;
+                    backtrack = Some((bp, bt + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+// Strips `prefix` from the entry and returns the rest, None when the entry is not
+// inside it. Each prefix component may be a glob like the member patterns of tar,
+// e.g. "*.app/" for the macOS bundle whose name changes between releases
+fn match_prefix(entry_name: &str, prefix: &str) -> Option<String> {
+    let mut rest = entry_name.replace('\\', "/");
+    let prefix = prefix.replace('\\', "/");
+    for pattern in prefix.split('/').filter(|p| !p.is_empty() && *p != ".") {
+        let trimmed = rest.trim_start_matches("./");
+        let (component, tail) = trimmed.split_once('/')?;
+        if !glob_match(pattern, component) {
+            return None;
+        }
+        rest = tail.to_string();
+    }
+    Some(rest)
+}
+
+fn should_extract(entry_name: &str, prefix: &str) -> bool {
+    match_prefix(entry_name, prefix).is_some()
+}
+
+// Symlinks unpacked by earlier entries must not redirect later ones outside of `base`
+fn ensure_no_symlinks(base: &Path, out_path: &Path, entry_name: &str) -> Result<(), UiError> {
+    let relative = out_path
+        .strip_prefix(base)
+        .map_err(|_| zip_slip(entry_name))?;
+    let mut current = base.to_path_buf();
+    let mut components = relative.components().peekable();
+    while let Some(component) = components.next() {
+        current.push(component);
+        // The entry itself may replace an old link, only its parents have to be real
+        if components.peek().is_none() {
+            break;
+        }
+        if fs::symlink_metadata(&current).is_ok_and(|m| m.file_type().is_symlink()) {
+            return Err(zip_slip(entry_name));
+        }
+    }
+    Ok(())
+}
+
+// Hard links name another archive member, which is already extracted inside `base`
+fn hard_link_source(
+    base: &Path,
+    target: &str,
+    strip: usize,
+    entry_name: &str,
+) -> Result<PathBuf, UiError> {
+    let source = strip_components(target, strip)
+        .ok_or_else(|| zip_slip(entry_name))
+        .and_then(|t| make_output_path(base, &t, ""))?;
+    ensure_no_symlinks(base, &source, entry_name)?;
+    Ok(source)
+}
+
+// Symlink targets are relative to the link's own directory and must stay inside `base`
+fn check_symlink_target(
+    base: &Path,
+    stripped: &str,
+    target: &str,
+    entry_name: &str,
+) -> Result<(), UiError> {
+    if target.starts_with('/') || has_drive_prefix(target) || Path::new(target).has_root() {
+        return Err(zip_slip(entry_name));
+    }
+    let joined = match Path::new(stripped).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => {
+            format!("{}/{target}", parent.to_string_lossy())
+        }
+        _ => target.to_string(),
+    };
+    normalize_relative(&joined)
+        .ok_or_else(|| zip_slip(entry_name))
+        .and_then(|t| make_output_path(base, &t, ""))
+        .map(|_| ())
+}
+
+fn open_tar_gz(filepath: &Path) -> Result<tar::Archive<GzDecoder<File>>, UiError> {
+    let file = File::open(filepath).map_err(|e| UiError::io("open tar error", e))?;
+    Ok(tar::Archive::new(GzDecoder::new(file)))
+}
+
+fn tar_entry_name<R: Read>(entry: &tar::Entry<R>) -> Result<String, UiError> {
+    entry
+        .path()
+        .map(|p| p.to_string_lossy().replace('\\', "/"))
+        .map_err(|e| UiError::parse("tar path error", e))
+}
+
+// Drops the first `strip` components, None when nothing is left (like tar does)
+fn strip_components(entry_name: &str, strip: usize) -> Option<String> {
+    let parts: Vec<&str> = entry_name
+        .split('/')
+        .filter(|p| !p.is_empty() && *p != ".")
+        .collect();
+    if parts.len() <= strip {
+        return None;
+    }
+    Some(parts[strip..].join("/"))
+}
+
+fn zip_slip(entry_name: &str) -> UiError {
+    UiError::new(
+        ErrorCode::ZipSlip,
+        format!("unsafe path in archive (zip slip): {entry_name}"),
+    )
+    .with_details(serde_json::json!({ "entry": entry_name }))
+}
+
+// "C:/..." points at another drive on Windows, no matter where we extract to
+fn has_drive_prefix(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':'
+}
+
+// Resolves "." and ".." lexically, None when the path would leave its root
+fn normalize_relative(path: &str) -> Option<String> {
+    if path.starts_with('/') || has_drive_prefix(path) {
+        return None;
     }
+    let mut parts: Vec<&str> = Vec::new();
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
+            }
+            p => parts.push(p),
+        }
+    }
+    Some(parts.join("/"))
 }
 
 // Removes the prefix folder from entry_name and joins under extract_dir
-fn make_output_path(base: &Path, entry_name: &str, prefix: &str) -> Result<PathBuf, String> {
-    let normalized = entry_name.replace('\\', "/");
-    let trimmed = if prefix.is_empty() {
-        normalized.as_str()
-    } else if let Some(stripped) = normalized.strip_prefix(prefix) {
-        stripped
-    } else {
-        return Err("entry does not match prefix".into());
+pub fn make_output_path(base: &Path, entry_name: &str, prefix: &str) -> Result<PathBuf, UiError> {
+    let Some(trimmed) = match_prefix(entry_name, prefix) else {
+        return Err(UiError::new(
+            ErrorCode::InvalidData,
+            format!("entry does not match prefix: {entry_name}"),
+        ));
     };
 
     // Prevent zip slip: disallow path traversal
-    let candidate = base.join(trimmed);
+    if has_drive_prefix(&trimmed)
+        || Path::new(&trimmed)
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(zip_slip(entry_name));
+    }
+    let candidate = base.join(&trimmed);
     let canon_base = dunce::canonicalize(base).unwrap_or_else(|_| base.to_path_buf());
     let canon_cand = dunce::canonicalize(&candidate).unwrap_or(candidate.clone());
 
     if !canon_cand.starts_with(&canon_base) {
-        return Err("unsafe path in zip (zip slip)".into());
+        return Err(zip_slip(entry_name));
     }
     Ok(candidate)
 }
 
 #[command]
-pub async fn get_download_links() -> Result<Value, UiError> {
-    let res = reqwest::get("https://vsapi.betterjs.dev/download")
-        .await
-        .map_err(|e| UiError::from(format!("Request error: {e}")))?
+pub async fn get_download_links(app: AppHandle) -> Result<Value, UiError> {
+    let api = http::api(&app);
+    let res = api
+        .client()
+        .get(api.url(Service::VsApi, "/download"))
+        .send()
+        .await?
         .text()
-        .await
-        .map_err(|e| UiError::from(format!("Read error: {e}")))?;
+        .await?;
     let json: Value =
-        serde_json::from_str(&res).map_err(|e| UiError::from(format!("JSON parse error: {e}")))?;
+        serde_json::from_str(&res).map_err(|e| UiError::parse("JSON parse error", e))?;
     Ok(json)
 }
 
 #[command]
-pub async fn get_download_link(version: &str) -> Result<String, UiError> {
+pub async fn get_download_link(app: AppHandle, version: &str) -> Result<String, UiError> {
+    get_download_info(app, version).await.map(|info| info.url)
+}
+
+#[derive(Debug, Serialize)]
+pub struct DownloadInfo {
+    pub url: String,
+    pub filesize: Option<u64>,
+    // In the "md5:<hex>" / "sha256:<hex>" form accepted by download_and_maybe_extract
+    pub hash: Option<String>,
+}
+
+#[command]
+pub async fn get_download_info(app: AppHandle, version: &str) -> Result<DownloadInfo, UiError> {
     // if platform is macos it should say mac
     let platform = tauri_plugin_os::platform().replace("macos", "mac");
-    let url = format!(
-        "https://vsapi.betterjs.dev/download/{}/{}/",
-        version, platform
+    let api = http::api(&app);
+    let url = api.url(
+        Service::VsApi,
+        &format!("/download/{}/{}/", version, platform),
     );
-    let res = get(&url)
-        .await
-        .map_err(|e| UiError::from(format!("Request error: {e}")))?
-        .text()
-        .await
-        .map_err(|e| UiError::from(format!("Read error: {e}")))?;
+    let res = api.client().get(url).send().await?.text().await?;
 
-    let json: serde_json::Value =
-        serde_json::from_str(&res).map_err(|e| UiError::from(format!("JSON parse error: {e}")))?;
-    if let Some(link) = json.get("url").and_then(|v| v.as_str()) {
-        Ok(link.to_string())
-    } else {
-        Err(UiError::from("No download_url found in response"))
+    let json: Value =
+        serde_json::from_str(&res).map_err(|e| UiError::parse("JSON parse error", e))?;
+    let link = json
+        .get("url")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| UiError::new(ErrorCode::InvalidData, "No download_url found in response"))?;
+    // The index reports the size either as a number of bytes or as a string
+    let filesize = json.get("filesize").and_then(|v| {
+        v.as_u64()
+            .or_else(|| v.as_str().and_then(|s| s.trim().parse().ok()))
+    });
+    let hash = ["sha256", "sha1", "md5"].iter().find_map(|algo| {
+        json.get(*algo)
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.is_empty())
+            .map(|s| format!("{algo}:{s}"))
+    });
+    Ok(DownloadInfo {
+        url: link.to_string(),
+        filesize,
+        hash,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A fresh directory per test, they run in parallel
+    fn temp_base(name: &str) -> PathBuf {
+        let base =
+            std::env::temp_dir().join(format!("vslauncher-download-{}-{name}", std::process::id()));
+        fs::remove_dir_all(&base).ok();
+        fs::create_dir_all(&base).unwrap();
+        base
+    }
+
+    fn code<T: std::fmt::Debug>(result: Result<T, UiError>) -> ErrorCode {
+        result.unwrap_err().code
+    }
+
+    #[test]
+    fn glob_matches_single_components() {
+        assert!(glob_match("*.app", "Vintage Story.app"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("v?", "v1"));
+        assert!(glob_match("vintagestory", "vintagestory"));
+        assert!(!glob_match("*.app", "Vintage Story.app.zip"));
+        assert!(!glob_match("v?", "v"));
+    }
+
+    #[test]
+    fn match_prefix_strips_glob_components() {
+        assert_eq!(
+            match_prefix("Vintage Story.app/Contents/MacOS/x", "*.app/").as_deref(),
+            Some("Contents/MacOS/x")
+        );
+        assert_eq!(match_prefix("./Foo.app/x", "*.app/").as_deref(), Some("x"));
+        assert_eq!(
+            match_prefix("vintagestory\\Mods\\a.zip", "vintagestory/").as_deref(),
+            Some("Mods/a.zip")
+        );
+        assert_eq!(match_prefix("a/b", "").as_deref(), Some("a/b"));
+        assert_eq!(match_prefix("other/x", "*.app/"), None);
+        // The bundle directory itself has nothing left to extract
+        assert_eq!(match_prefix("Foo.app", "*.app/"), None);
+        assert!(should_extract("Foo.app/x", "*.app/"));
+        assert!(!should_extract("Foo.apps/x", "*.app/"));
+    }
+
+    #[test]
+    fn strip_components_like_tar() {
+        assert_eq!(strip_components("a/b/c", 1).as_deref(), Some("b/c"));
+        assert_eq!(strip_components("./a/b", 1).as_deref(), Some("b"));
+        assert_eq!(strip_components("a/b", 0).as_deref(), Some("a/b"));
+        assert_eq!(strip_components("a/", 1), None);
+        assert_eq!(strip_components("a/b", 2), None);
+        assert_eq!(strip_components("a/b", 5), None);
+    }
+
+    #[test]
+    fn normalize_relative_stays_inside_root() {
+        assert_eq!(normalize_relative("a/./b/../c").as_deref(), Some("a/c"));
+        assert_eq!(normalize_relative("a/..").as_deref(), Some(""));
+        assert_eq!(normalize_relative("../x"), None);
+        assert_eq!(normalize_relative("a/../../x"), None);
+        assert_eq!(normalize_relative("/etc/passwd"), None);
+        assert_eq!(normalize_relative("C:/Windows"), None);
+    }
+
+    #[test]
+    fn make_output_path_rejects_escapes() {
+        let base = temp_base("output");
+        assert_eq!(
+            make_output_path(&base, "Mods/a.zip", "").unwrap(),
+            base.join("Mods/a.zip")
+        );
+        assert_eq!(
+            make_output_path(&base, "Foo.app/Contents/x", "*.app/").unwrap(),
+            base.join("Contents/x")
+        );
+        for entry in [
+            "../evil",
+            "a/../../evil",
+            "a\\..\\..\\evil",
+            "/etc/passwd",
+            "C:/Windows/evil.dll",
+            "C:\\Windows\\evil.dll",
+            "d:evil",
+        ] {
+            assert_eq!(
+                code(make_output_path(&base, entry, "")),
+                ErrorCode::ZipSlip,
+                "{entry}"
+            );
+        }
+        assert_eq!(
+            code(make_output_path(&base, "other/x", "*.app/")),
+            ErrorCode::InvalidData
+        );
+        fs::remove_dir_all(&base).ok();
+    }
+
+    #[test]
+    fn hard_links_stay_inside_base() {
+        let base = temp_base("hardlink");
+        assert_eq!(
+            hard_link_source(&base, "root/lib/a.so", 1, "x").unwrap(),
+            base.join("lib/a.so")
+        );
+        // Like tar, the leading "/" is dropped with the other stripped components
+        assert_eq!(
+            hard_link_source(&base, "/etc/passwd", 1, "x").unwrap(),
+            base.join("passwd")
+        );
+        for target in ["root/../../etc/passwd", "root/C:/x"] {
+            assert_eq!(
+                code(hard_link_source(&base, target, 1, "x")),
+                ErrorCode::ZipSlip,
+                "{target}"
+            );
+        }
+        // Stripped down to nothing
+        assert_eq!(
+            code(hard_link_source(&base, "root", 1, "x")),
+            ErrorCode::ZipSlip
+        );
+        fs::remove_dir_all(&base).ok();
+    }
+
+    #[test]
+    fn symlinks_stay_inside_base() {
+        let base = temp_base("symlink");
+        check_symlink_target(&base, "lib/a.so", "a.so.1", "x").unwrap();
+        check_symlink_target(&base, "lib/a.so", "../bin/a", "x").unwrap();
+        check_symlink_target(&base, "a.so", "a.so.1", "x").unwrap();
+        for (stripped, target) in [
+            ("lib/a.so", "../../etc/passwd"),
+            ("a.so", "../a.so"),
+            ("lib/a.so", "/etc/passwd"),
+            ("lib/a.so", "C:/Windows/evil.dll"),
+            ("lib/a.so", "C:\\Windows\\evil.dll"),
+        ] {
+            assert_eq!(
+                code(check_symlink_target(&base, stripped, target, "x")),
+                ErrorCode::ZipSlip,
+                "{stripped} -> {target}"
+            );
+        }
+        fs::remove_dir_all(&base).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn extracted_symlinks_are_not_followed() {
+        let base = temp_base("follow");
+        let outside = temp_base("follow-outside");
+        fs::create_dir_all(base.join("real")).unwrap();
+        std::os::unix::fs::symlink(&outside, base.join("link")).unwrap();
+
+        ensure_no_symlinks(&base, &base.join("real/file"), "x").unwrap();
+        // Replacing the link itself is fine, writing through it is not
+        ensure_no_symlinks(&base, &base.join("link"), "x").unwrap();
+        assert_eq!(
+            code(ensure_no_symlinks(&base, &base.join("link/file"), "x")),
+            ErrorCode::ZipSlip
+        );
+        assert_eq!(
+            code(hard_link_source(&base, "root/link/file", 1, "x")),
+            ErrorCode::ZipSlip
+        );
+        assert_eq!(
+            code(ensure_no_symlinks(&base, &outside.join("file"), "x")),
+            ErrorCode::ZipSlip
+        );
+        fs::remove_dir_all(&base).ok();
+        fs::remove_dir_all(&outside).ok();
     }
 }
@@ -1,25 +1,136 @@
 use serde::Serialize;
+use serde_json::{json, Value};
+use std::{error::Error, fmt::Display};
+
+// Machine readable error codes, serialized as e.g. "not_found"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    Network,
+    HttpStatus,
+    Io,
+    Parse,
+    NotFound,
+    InvalidParams,
+    InvalidData,
+    InvalidState,
+    AuthRequired,
+    TotpRequired,
+    InvalidLogin,
+    InvalidSession,
+    ZipSlip,
+    Verification,
+    LaunchFailed,
+    Unsupported,
+    Cancelled,
+    Paused,
+    Offline,
+    Unknown,
+}
 
 #[derive(Debug, Serialize)]
 pub struct UiError {
-    pub name: String,
+    pub code: ErrorCode,
     pub message: String,
+    // Structured context for the frontend, e.g. the HTTP status or a prelogin token
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<Value>,
+    // Messages of the underlying errors, outermost first
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<String>,
 }
 
-impl From<String> for UiError {
-    fn from(s: String) -> Self {
+impl UiError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
         UiError {
-            name: "UNKNOWN".into(),
-            message: s,
+            code,
+            message: message.into(),
+            details: None,
+            sources: vec![],
+        }
+    }
+
+    // Wraps a lower level error as "<context>: <error>" and records its source chain
+    pub fn wrap<E: Error>(code: ErrorCode, context: impl Display, err: E) -> Self {
+        let mut sources = vec![];
+        let mut source = err.source();
+        while let Some(s) = source {
+            sources.push(s.to_string());
+            source = s.source();
+        }
+        UiError {
+            code,
+            message: format!("{context}: {err}"),
+            details: None,
+            sources,
+        }
+    }
+
+    pub fn io<E: Error>(context: impl Display, err: E) -> Self {
+        Self::wrap(ErrorCode::Io, context, err)
+    }
+
+    pub fn parse<E: Error>(context: impl Display, err: E) -> Self {
+        Self::wrap(ErrorCode::Parse, context, err)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::NotFound, message)
+    }
+
+    pub fn http_status(status: reqwest::StatusCode) -> Self {
+        Self::new(ErrorCode::HttpStatus, format!("HTTP error: {status}"))
+            .with_details(json!({ "status": status.as_u16() }))
+    }
+
+    pub fn with_details(mut self, details: Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+}
+
+impl Display for UiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl From<reqwest::Error> for UiError {
+    fn from(e: reqwest::Error) -> Self {
+        match e.status() {
+            Some(status) => UiError::http_status(status),
+            None if e.is_decode() => UiError::parse("Parse error", e),
+            None => UiError::wrap(ErrorCode::Network, "Request error", e),
         }
     }
 }
 
+impl From<std::io::Error> for UiError {
+    fn from(e: std::io::Error) -> Self {
+        UiError::io("I/O error", e)
+    }
+}
+
+impl From<serde_json::Error> for UiError {
+    fn from(e: serde_json::Error) -> Self {
+        UiError::parse("JSON parse error", e)
+    }
+}
+
+impl From<tauri::Error> for UiError {
+    fn from(e: tauri::Error) -> Self {
+        UiError::wrap(ErrorCode::Unknown, "Tauri error", e)
+    }
+}
+
+impl From<String> for UiError {
+    fn from(s: String) -> Self {
+        UiError::new(ErrorCode::Unknown, s)
+    }
+}
+
 impl From<&str> for UiError {
     fn from(s: &str) -> Self {
-        UiError {
-            name: "UNKNOWN".into(),
-            message: s.to_string(),
-        }
+        UiError::new(ErrorCode::Unknown, s)
     }
 }
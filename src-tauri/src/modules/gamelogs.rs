@@ -0,0 +1,177 @@
+use serde::Serialize;
+use std::{
+    fs::{self, File},
+    io::{BufRead, BufReader, Read, Write},
+    path::{Path, PathBuf},
+    process::Child,
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+use tauri::{command, AppHandle, Emitter, Manager, Runtime};
+
+use super::errors::{ErrorCode, UiError};
+use super::util::{self, millis};
+
+// Older launch logs of an installation are removed once there are more than this
+const MAX_LOGS_PER_INSTALLATION: usize = 10;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LogLine {
+    pub pid: u32,
+    pub installation_id: u64,
+    // "stdout" | "stderr"
+    pub stream: &'static str,
+    pub line: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LaunchLog {
+    pub name: String,
+    pub size: u64,
+    // Milliseconds since the epoch
+    pub modified: u64,
+}
+
+fn logs_dir<R: Runtime>(app: &AppHandle<R>, installation_id: u64) -> Result<PathBuf, UiError> {
+    Ok(app
+        .path()
+        .app_data_dir()
+        .map_err(|e| UiError::io("Failed to resolve app data dir", e))?
+        .join("logs")
+        .join(installation_id.to_string()))
+}
+
+fn valid_name(name: &str) -> bool {
+    util::valid_generated_name(name, "launch-", ".log")
+}
+
+fn collect_logs(dir: &Path) -> Result<Vec<LaunchLog>, UiError> {
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+    let mut logs = vec![];
+    for entry in fs::read_dir(dir).map_err(|e| UiError::io("Failed to read logs directory", e))? {
+        let entry = entry.map_err(|e| UiError::io("Failed to read logs directory", e))?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !valid_name(&name) {
+            continue;
+        }
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        logs.push(LaunchLog {
+            name,
+            size: meta.len(),
+            modified: meta.modified().map(millis).unwrap_or(0),
+        });
+    }
+    // Newest first, names embed the launch time so they sort the same way
+    logs.sort_by(|a, b| b.name.cmp(&a.name));
+    Ok(logs)
+}
+
+fn rotate(dir: &Path) {
+    let Ok(logs) = collect_logs(dir) else {
+        return;
+    };
+    // Leave room for the log that is about to be created
+    for log in logs.iter().skip(MAX_LOGS_PER_INSTALLATION - 1) {
+        fs::remove_file(dir.join(&log.name)).ok();
+    }
+}
+
+fn open_log<R: Runtime>(app: &AppHandle<R>, installation_id: u64) -> Result<File, UiError> {
+    let dir = logs_dir(app, installation_id)?;
+    fs::create_dir_all(&dir).map_err(|e| UiError::io("Failed to create logs directory", e))?;
+    rotate(&dir);
+    let path = dir.join(format!("launch-{}.log", millis(SystemTime::now())));
+    File::create(&path).map_err(|e| UiError::io("Failed to create launch log", e))
+}
+
+fn pump<R: Runtime>(
+    app: AppHandle<R>,
+    pid: u32,
+    installation_id: u64,
+    stream: &'static str,
+    source: impl Read + Send + 'static,
+    file: Option<Arc<Mutex<File>>>,
+) {
+    std::thread::spawn(move || {
+        let mut reader = BufReader::new(source);
+        let mut buf = Vec::new();
+        // The game does not promise UTF-8, so read raw lines and convert lossily
+        while let Ok(n) = reader.read_until(b'\n', &mut buf) {
+            if n == 0 {
+                break;
+            }
+            let line = String::from_utf8_lossy(&buf).trim_end().to_string();
+            buf.clear();
+            if let Some(file) = &file {
+                let mut file = file.lock().unwrap();
+                writeln!(file, "[{stream}] {line}").ok();
+            }
+            app.emit(
+                "game://log",
+                LogLine {
+                    pid,
+                    installation_id,
+                    stream,
+                    line,
+                },
+            )
+            .ok();
+        }
+    });
+}
+
+// Drains the piped stdout/stderr of a launched game into a new launch log and
+// streams every line to the UI. The pipes are drained even if the log can't be
+// written, otherwise the game would block once they fill up.
+pub fn capture<R: Runtime>(app: &AppHandle<R>, installation_id: u64, child: &mut Child) {
+    let file = match open_log(app, installation_id) {
+        Ok(file) => Some(Arc::new(Mutex::new(file))),
+        Err(e) => {
+            log::warn!("{e}");
+            None
+        }
+    };
+    let pid = child.id();
+    if let Some(stdout) = child.stdout.take() {
+        pump(
+            app.clone(),
+            pid,
+            installation_id,
+            "stdout",
+            stdout,
+            file.clone(),
+        );
+    }
+    if let Some(stderr) = child.stderr.take() {
+        pump(app.clone(), pid, installation_id, "stderr", stderr, file);
+    }
+}
+
+#[command]
+pub fn list_launch_logs(app: AppHandle, installation_id: u64) -> Result<Vec<LaunchLog>, UiError> {
+    collect_logs(&logs_dir(&app, installation_id)?)
+}
+
+#[command]
+pub fn read_launch_log(
+    app: AppHandle,
+    installation_id: u64,
+    name: String,
+) -> Result<String, UiError> {
+    if !valid_name(&name) {
+        return Err(UiError::new(
+            ErrorCode::InvalidParams,
+            format!("Invalid log name: {name}"),
+        ));
+    }
+    let path = logs_dir(&app, installation_id)?.join(&name);
+    if !path.exists() {
+        return Err(UiError::not_found(format!("Launch log not found: {name}")));
+    }
+    let bytes = fs::read(&path).map_err(|e| UiError::io("Failed to read launch log", e))?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
@@ -0,0 +1,313 @@
+use reqwest::header::{
+    HeaderMap, HeaderValue, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+};
+use reqwest::{Certificate, Client, Proxy, Request, RequestBuilder, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{fs, path::PathBuf, sync::RwLock, time::Duration};
+use tauri::{command, AppHandle, Manager, Runtime, State};
+
+use super::cache::{self, CachePolicy, CachedResponse};
+use super::errors::{ErrorCode, UiError};
+
+const CONFIG_FILE: &str = "http.json";
+
+// The remote services the launcher talks to
+#[derive(Debug, Clone, Copy)]
+pub enum Service {
+    VsApi,
+    ModDb,
+    Auth,
+    MasterServer,
+    News,
+}
+
+// Overridable to point the launcher at mirrors or a local mock server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BaseUrls {
+    pub vsapi: String,
+    pub moddb: String,
+    pub auth: String,
+    pub masterserver: String,
+    pub news: String,
+}
+
+impl Default for BaseUrls {
+    fn default() -> Self {
+        BaseUrls {
+            vsapi: "https://vsapi.betterjs.dev".into(),
+            moddb: "https://mods.vintagestory.at".into(),
+            auth: "https://auth3.vintagestory.at".into(),
+            masterserver: "https://masterserver.vintagestory.at".into(),
+            news: "https://www.vintagestory.at".into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpConfig {
+    pub base_urls: BaseUrls,
+    pub user_agent: String,
+    pub connect_timeout_secs: u64,
+    // Time allowed between two reads, not for the whole request, so big downloads still work
+    pub read_timeout_secs: u64,
+    // e.g. "http://127.0.0.1:8080" or "socks5h://host:1080". Without one the
+    // HTTP_PROXY / HTTPS_PROXY / ALL_PROXY environment variables are honored
+    pub proxy: Option<String>,
+    // PEM file with extra root certificates, e.g. for a mock server or an intercepting proxy
+    pub ca_cert: Option<String>,
+    // Serve the last cached responses instead of going to the network
+    pub offline: bool,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        HttpConfig {
+            base_urls: BaseUrls::default(),
+            user_agent: concat!("StoryForge/", env!("CARGO_PKG_VERSION")).into(),
+            connect_timeout_secs: 15,
+            read_timeout_secs: 60,
+            proxy: None,
+            ca_cert: None,
+            offline: false,
+        }
+    }
+}
+
+// A configured client plus the base urls it was built with; cheap to clone
+#[derive(Clone)]
+pub struct Api {
+    client: Client,
+    urls: BaseUrls,
+    offline: bool,
+}
+
+impl Api {
+    fn build(config: &HttpConfig) -> Result<Self, UiError> {
+        let mut builder = Client::builder()
+            .user_agent(&config.user_agent)
+            .connect_timeout(Duration::from_secs(config.connect_timeout_secs))
+            .read_timeout(Duration::from_secs(config.read_timeout_secs));
+        if let Some(proxy) = config.proxy.as_deref().filter(|p| !p.trim().is_empty()) {
+            let proxy = Proxy::all(proxy.trim())
+                .map_err(|e| UiError::wrap(ErrorCode::InvalidParams, "Invalid proxy", e))?;
+            builder = builder.proxy(proxy);
+        }
+        if let Some(path) = config.ca_cert.as_deref().filter(|p| !p.trim().is_empty()) {
+            let pem =
+                fs::read(path).map_err(|e| UiError::io("Failed to read CA certificate", e))?;
+            let certs = Certificate::from_pem_bundle(&pem).map_err(|e| {
+                UiError::wrap(ErrorCode::InvalidParams, "Invalid CA certificate", e)
+            })?;
+            for cert in certs {
+                builder = builder.add_root_certificate(cert);
+            }
+        }
+        let client = builder
+            .build()
+            .map_err(|e| UiError::wrap(ErrorCode::InvalidParams, "Invalid HTTP settings", e))?;
+        Ok(Api {
+            client,
+            urls: config.base_urls.clone(),
+            offline: config.offline,
+        })
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    pub fn offline(&self) -> bool {
+        self.offline
+    }
+
+    // `path` starts with a slash, e.g. url(Service::ModDb, "/api/mods")
+    pub fn url(&self, service: Service, path: &str) -> String {
+        let base = match service {
+            Service::VsApi => &self.urls.vsapi,
+            Service::ModDb => &self.urls.moddb,
+            Service::Auth => &self.urls.auth,
+            Service::MasterServer => &self.urls.masterserver,
+            Service::News => &self.urls.news,
+        };
+        format!("{}{}", base.trim_end_matches('/'), path)
+    }
+}
+
+// Shared by all commands so connections are pooled; replaced whenever the settings change
+pub struct Http {
+    config: RwLock<HttpConfig>,
+    api: RwLock<Api>,
+}
+
+fn config_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, UiError> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(CONFIG_FILE))
+        .map_err(|e| UiError::io("Failed to resolve app data dir", e))
+}
+
+impl Http {
+    // Falls back to the defaults when the saved settings are missing or unusable,
+    // the launcher has to be able to reach its services to let the user fix them
+    pub fn load<R: Runtime>(app: &AppHandle<R>) -> Self {
+        let saved = config_path(app)
+            .ok()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|text| match serde_json::from_str::<HttpConfig>(&text) {
+                Ok(config) => Some(config),
+                Err(e) => {
+                    log::warn!("Ignoring invalid HTTP settings: {e}");
+                    None
+                }
+            });
+        let built = saved.and_then(|config| match Api::build(&config) {
+            Ok(api) => Some((config, api)),
+            Err(e) => {
+                log::warn!("Ignoring invalid HTTP settings: {e}");
+                None
+            }
+        });
+        let (config, api) = built.unwrap_or_else(|| {
+            let config = HttpConfig::default();
+            let api = Api::build(&config).expect("default HTTP settings are valid");
+            (config, api)
+        });
+        Http {
+            config: RwLock::new(config),
+            api: RwLock::new(api),
+        }
+    }
+}
+
+pub fn api<R: Runtime>(app: &AppHandle<R>) -> Api {
+    app.state::<Http>().api.read().unwrap().clone()
+}
+
+pub fn offline_error(url: &str) -> UiError {
+    UiError::new(
+        ErrorCode::Offline,
+        format!("Offline mode is on and {url} has not been cached yet"),
+    )
+    .with_details(json!({ "url": url }))
+}
+
+fn header(headers: &HeaderMap, name: impl reqwest::header::AsHeaderName) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+}
+
+// Fetches a fresh copy, conditionally when there is a cached one to confirm
+async fn revalidate<R: Runtime>(
+    app: &AppHandle<R>,
+    api: &Api,
+    mut request: Request,
+    cached: Option<&CachedResponse>,
+) -> Result<String, UiError> {
+    let url = request.url().to_string();
+    if let Some(cached) = cached {
+        let headers = request.headers_mut();
+        if let Some(etag) = cached.etag.as_deref() {
+            if let Ok(value) = HeaderValue::from_str(etag) {
+                headers.insert(IF_NONE_MATCH, value);
+            }
+        }
+        if let Some(last_modified) = cached.last_modified.as_deref() {
+            if let Ok(value) = HeaderValue::from_str(last_modified) {
+                headers.insert(IF_MODIFIED_SINCE, value);
+            }
+        }
+    }
+    let res = api.client.execute(request).await?;
+    let etag = header(res.headers(), ETAG);
+    let last_modified = header(res.headers(), LAST_MODIFIED);
+    if let (StatusCode::NOT_MODIFIED, Some(cached)) = (res.status(), cached) {
+        let entry = CachedResponse::new(
+            url,
+            cached.body.clone(),
+            etag.or_else(|| cached.etag.clone()),
+            last_modified.or_else(|| cached.last_modified.clone()),
+        );
+        cache::store(app, &entry);
+        return Ok(entry.body);
+    }
+    if !res.status().is_success() {
+        return Err(UiError::http_status(res.status()));
+    }
+    let entry = CachedResponse::new(url, res.text().await?, etag, last_modified);
+    cache::store(app, &entry);
+    Ok(entry.body)
+}
+
+// Serves the request from the on-disk cache while it is fresh. A stale copy is still
+// served within the policy's stale window while a background fetch refreshes it, and
+// whenever the server can't be reached. In offline mode only cached copies are served.
+pub async fn cached_text<R: Runtime>(
+    app: &AppHandle<R>,
+    api: &Api,
+    request: RequestBuilder,
+    policy: CachePolicy,
+) -> Result<String, UiError> {
+    let request = request.build()?;
+    let url = request.url().to_string();
+    let cached = cache::load(app, &url);
+    if api.offline {
+        return cached
+            .map(|entry| entry.body)
+            .ok_or_else(|| offline_error(&url));
+    }
+    let Some(cached) = cached else {
+        return revalidate(app, api, request, None).await;
+    };
+
+    let age = cached.age();
+    if age < policy.ttl {
+        return Ok(cached.body);
+    }
+    if age < policy.ttl + policy.stale {
+        let body = cached.body.clone();
+        let (app, api) = (app.clone(), api.clone());
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = revalidate(&app, &api, request, Some(&cached)).await {
+                log::warn!("Failed to refresh {}: {e}", cached.url);
+            }
+        });
+        return Ok(body);
+    }
+    match revalidate(app, api, request, Some(&cached)).await {
+        Ok(body) => Ok(body),
+        Err(e) if matches!(e.code, ErrorCode::Network | ErrorCode::HttpStatus) => {
+            log::warn!("Serving stale copy of {url}: {e}");
+            Ok(cached.body)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+#[command]
+pub fn get_http_config(http: State<'_, Http>) -> HttpConfig {
+    http.config.read().unwrap().clone()
+}
+
+// Validates by building the client first, so bad settings are never saved
+#[command]
+pub fn set_http_config(
+    app: AppHandle,
+    http: State<'_, Http>,
+    config: HttpConfig,
+) -> Result<HttpConfig, UiError> {
+    let api = Api::build(&config)?;
+    let path = config_path(&app)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| UiError::io("Failed to create directory", e))?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(&config)?)
+        .map_err(|e| UiError::io("Failed to save HTTP settings", e))?;
+    *http.api.write().unwrap() = api;
+    *http.config.write().unwrap() = config.clone();
+    Ok(config)
+}
@@ -1,24 +1,29 @@
 use serde::Deserialize;
-use serde_json::{json, Value};
+use serde_json::{json, Map, Value};
 use std::{
     fs::File,
     io::Read,
     path::{Path, PathBuf},
-    process::Command,
+    process::{Command, Stdio},
 };
-use tauri::{command, AppHandle, Manager};
-use tauri_plugin_zustand::ManagerExt;
+use tauri::{command, AppHandle, Emitter, Manager};
 
-use super::errors::UiError;
+use super::auth;
+use super::backups;
+use super::download::ProgressPayload;
+use super::errors::{ErrorCode, UiError};
+use super::gamelogs;
+use super::http;
+use super::mods;
+use super::processes;
+use super::store::{self, Installation};
+use super::util;
 
 #[command]
 pub async fn initialize_game(path: String) -> Result<String, UiError> {
     let pb = PathBuf::from(path).join("Mods");
     if !pb.exists() {
-        std::fs::create_dir_all(&pb).map_err(|e| UiError {
-            name: "create_dir_failed".into(),
-            message: format!("Failed to create directory: {e}"),
-        })?;
+        std::fs::create_dir_all(&pb).map_err(|e| UiError::io("Failed to create directory", e))?;
     }
     Ok("initialized".into())
 }
@@ -29,10 +34,9 @@ pub fn confirm_vintage_story_exe(path: String) -> Result<String, UiError> {
     if pb.exists() && pb.is_file() {
         Ok(pb.to_string_lossy().into_owned())
     } else {
-        Err(UiError {
-            name: "not_found".into(),
-            message: "Could not find Vintage Story executable.".into(),
-        })
+        Err(UiError::not_found(
+            "Could not find Vintage Story executable.",
+        ))
     }
 }
 
@@ -41,48 +45,33 @@ pub struct PlayGameParams {
     pub installation_id: u64,
     pub server: Option<String>,
     pub password: Option<String>,
+    // Back up the worlds first when the mods changed since the last backup
+    pub backup_saves: Option<bool>,
 }
 
 #[command]
-pub fn play_game(app: AppHandle, options: Option<PlayGameParams>) -> Result<String, UiError> {
-    let options = options.ok_or_else(|| UiError {
-        name: "invalid_params".into(),
-        message: "Invalid play game parameters.".into(),
-    })?;
-    let installation_zustand = app.zustand().get("installations", "installations").unwrap();
-    let installation_json: Value = serde_json::from_value(installation_zustand).unwrap();
-    // Find installation with matching id
-    let installation = installation_json
-        .as_array()
-        .and_then(|arr| {
-            arr.iter()
-                .find(|inst| inst["id"].as_u64() == Some(options.installation_id))
-        })
-        .ok_or_else(|| UiError {
-            name: "not_found".into(),
-            message: format!("Installation with id {} not found", options.installation_id),
-        })?;
+pub async fn play_game(app: AppHandle, options: Option<PlayGameParams>) -> Result<String, UiError> {
+    let options = options
+        .ok_or_else(|| UiError::new(ErrorCode::InvalidParams, "Invalid play game parameters."))?;
+    let installation = store::find(&app, options.installation_id)?;
     let version_path = app
         .path()
         .app_data_dir()
-        .unwrap()
+        .map_err(|e| UiError::io("Failed to resolve app data dir", e))?
         .join("versions")
-        .join(installation["version"].as_str().unwrap());
+        .join(&installation.version);
     if !version_path.exists() || !version_path.is_dir() {
-        return Err(UiError {
-            name: "not_found".into(),
-            message: format!(
-                "Version directory not found: {}",
-                version_path.to_string_lossy()
-            ),
-        });
+        return Err(UiError::not_found(format!(
+            "Version directory not found: {}",
+            version_path.to_string_lossy()
+        )));
     }
-    let pb = PathBuf::from(installation["path"].as_str().unwrap());
-    let start_params = installation["startParams"].as_str().unwrap_or("");
+    let pb = PathBuf::from(&installation.path);
+    let start_params = installation.start_params.as_str();
     let mut found_exe = false;
     let mut combined_path = PathBuf::from("/");
     for entry in walkdir::WalkDir::new(&version_path) {
-        let entry = entry.map_err(|e| UiError::from(format!("walkdir error: {e}")))?;
+        let entry = entry.map_err(|e| UiError::io("walkdir error", e))?;
         if entry.file_type().is_file() {
             let fname = entry.file_name().to_string_lossy();
             if fname.eq_ignore_ascii_case("vintagestory")
@@ -95,36 +84,27 @@ pub fn play_game(app: AppHandle, options: Option<PlayGameParams>) -> Result<Stri
         }
     }
     if !found_exe {
-        return Err(UiError::from(
+        return Err(UiError::not_found(
             "Could not find Vintage Story executable in installation path",
         ));
     }
     if !combined_path.exists() || !combined_path.is_file() {
-        return Err(UiError {
-            name: "not_found".into(),
-            message: format!("Launch file not found: {}", combined_path.to_string_lossy()),
-        });
+        return Err(UiError::not_found(format!(
+            "Launch file not found: {}",
+            combined_path.to_string_lossy()
+        )));
     }
-    let account_zustand = app
-        .zustand()
-        .get("accounts", "selectedUser")
-        .ok_or_else(|| UiError {
-            name: "no_account".into(),
-            message: "No account selected".into(),
-        })?;
-    let account: Value = serde_json::from_value(account_zustand).unwrap();
-    if account.is_null() {
-        return Err(UiError {
-            name: "no_account".into(),
-            message: "No account selected".into(),
-        });
+    let account = auth::selected_account(&app)?;
+    // In offline mode there is no auth server to ask
+    if !http::api(&app).offline() {
+        auth::ensure_valid_session(&app, &account).await?;
     }
     let settings = json!({
         "stringSettings": {
-            "playeruid": account["uid"].as_str().unwrap_or(""),
-            "sessionkey": account["sessionkey"].as_str().unwrap_or(""),
-            "sessionsignature": account["sessionsignature"].as_str().unwrap_or(""),
-            "playername": account["playername"].as_str().unwrap_or(""),
+            "playeruid": account.uid,
+            "sessionkey": account.sessionkey,
+            "sessionsignature": account.sessionsignature,
+            "playername": account.playername,
         }
     });
     let settings_path = pb.join("clientsettings.json");
@@ -133,10 +113,7 @@ pub fn play_game(app: AppHandle, options: Option<PlayGameParams>) -> Result<Stri
         let mut existing_settings = String::new();
         File::open(&settings_path)
             .and_then(|mut f| f.read_to_string(&mut existing_settings))
-            .map_err(|e| UiError {
-                name: "read_failed".into(),
-                message: format!("Failed to read existing clientsettings.json: {e}"),
-            })?;
+            .map_err(|e| UiError::io("Failed to read existing clientsettings.json", e))?;
         let mut existing_json: Value =
             serde_json::from_str(&existing_settings).unwrap_or(json!({}));
         if let Some(obj) = existing_json.as_object_mut() {
@@ -155,48 +132,20 @@ pub fn play_game(app: AppHandle, options: Option<PlayGameParams>) -> Result<Stri
             &settings_path,
             serde_json::to_string_pretty(&existing_json).unwrap(),
         )
-        .map_err(|e| UiError {
-            name: "write_failed".into(),
-            message: format!("Failed to write clientsettings.json: {e}"),
-        })?;
-        return Command::new(&combined_path.as_os_str())
-            .args(&["--dataPath", &pb.as_path().to_string_lossy()])
-            .args(
-                &options
-                    .server
-                    .as_ref()
-                    .map(|s| vec!["--connect", s.as_str()])
-                    .unwrap_or_default(),
-            )
-            .args(
-                &options
-                    .password
-                    .as_ref()
-                    .map(|p| vec!["--password", p.as_str()])
-                    .unwrap_or_default(),
-            )
-            .args(&start_params.split_whitespace().collect::<Vec<&str>>())
-            .spawn()
-            .map_err(|e| UiError {
-                name: "launch_failed".into(),
-                message: format!("Failed to launch: {e}"),
-            })
-            .map(|_| "started".into());
+        .map_err(|e| UiError::io("Failed to write clientsettings.json", e))?;
     } else {
-        std::fs::create_dir_all(&settings_path.parent().unwrap()).map_err(|e| UiError {
-            name: "create_dir_failed".into(),
-            message: format!("Failed to create directory for clientsettings.json: {e}"),
-        })?;
+        std::fs::create_dir_all(&settings_path.parent().unwrap())
+            .map_err(|e| UiError::io("Failed to create directory for clientsettings.json", e))?;
         std::fs::write(
             &settings_path,
             serde_json::to_string_pretty(&settings).unwrap(),
         )
-        .map_err(|e| UiError {
-            name: "write_failed".into(),
-            message: format!("Failed to write clientsettings.json: {e}"),
-        })?;
+        .map_err(|e| UiError::io("Failed to write clientsettings.json", e))?;
     }
-    Command::new(&combined_path)
+    if options.backup_saves.unwrap_or(false) {
+        backups::backup_if_mods_changed(&app, installation.id)?;
+    }
+    let mut child = Command::new(&combined_path)
         .args(&["--dataPath", &pb.as_path().to_string_lossy()])
         .args(
             &options
@@ -213,11 +162,12 @@ pub fn play_game(app: AppHandle, options: Option<PlayGameParams>) -> Result<Stri
                 .unwrap_or_default(),
         )
         .args(&start_params.split_whitespace().collect::<Vec<&str>>())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
         .spawn()
-        .map_err(|e| UiError {
-            name: "launch_failed".into(),
-            message: format!("Failed to launch: {e}"),
-        })?;
+        .map_err(|e| UiError::wrap(ErrorCode::LaunchFailed, "Failed to launch", e))?;
+    gamelogs::capture(&app, installation.id, &mut child);
+    processes::track(&app, installation.id, child);
     Ok("started".into())
 }
 
@@ -231,24 +181,24 @@ pub fn reveal_in_file_explorer(path: String) -> Result<String, UiError> {
             Command::new("explorer")
                 .args(["/select,", &path.as_os_str().to_string_lossy()])
                 .status()
-                .map_err(|e| UiError::from(format!("Failed to open explorer: {e}")))?;
+                .map_err(|e| UiError::io("Failed to open explorer", e))?;
         } else {
             Command::new("explorer")
                 .arg(path.as_os_str().to_string_lossy().into_owned())
                 .status()
-                .map_err(|e| UiError::from(format!("Failed to open explorer: {e}")))?;
+                .map_err(|e| UiError::io("Failed to open explorer", e))?;
         }
     } else if cfg!(target_os = "macos") {
         if path.is_dir() {
             Command::new("open")
                 .arg(&path.as_os_str())
                 .status()
-                .map_err(|e| UiError::from(format!("Failed to open Finder: {e}")))?;
+                .map_err(|e| UiError::io("Failed to open Finder", e))?;
         } else {
             Command::new("open")
                 .args(["-R", &path.as_os_str().to_string_lossy()])
                 .status()
-                .map_err(|e| UiError::from(format!("Failed to open Finder: {e}")))?;
+                .map_err(|e| UiError::io("Failed to open Finder", e))?;
         }
     } else if cfg!(target_os = "linux") {
         // Try xdg-open for general desktops.
@@ -301,49 +251,176 @@ pub fn reveal_in_file_explorer(path: String) -> Result<String, UiError> {
                 }
             }
             if !launched {
-                return Err(UiError {
-                    name: "no_file_manager".into(),
-                    message: "Could not find a file manager to open the path.".into(),
-                });
+                return Err(UiError::new(
+                    ErrorCode::Unsupported,
+                    "Could not find a file manager to open the path.",
+                ));
             }
         }
     } else {
-        return Err(UiError {
-            name: "unsupported_platform".into(),
-            message: "This platform is not supported for revealing files.".into(),
-        });
+        return Err(UiError::new(
+            ErrorCode::Unsupported,
+            "This platform is not supported for revealing files.",
+        ));
     }
 
     Ok(path.as_os_str().to_string_lossy().to_string())
 }
 
 #[command]
-pub fn remove_installation(app: AppHandle, id: i64) -> Result<String, UiError> {
-    let installations_zustand = app.zustand().get("installations", "installations").unwrap();
-    let mut installations_json: Value = serde_json::from_value(installations_zustand).unwrap();
-    let installations_array = installations_json.as_array_mut().ok_or_else(|| UiError {
-        name: "invalid_data".into(),
-        message: "Installations data is not an array".into(),
-    })?;
-    let index = installations_array
-        .iter()
-        .position(|inst| inst["id"].as_i64() == Some(id));
-    if let Some(idx) = index {
-        let installation = &installations_array[idx];
-        let path = installation["path"].as_str().unwrap_or("");
-        // Remove the installation directory
-        let pb = PathBuf::from(path);
-        if pb.exists() && pb.is_dir() {
-            std::fs::remove_dir_all(&pb).map_err(|e| UiError {
-                name: "remove_failed".into(),
-                message: format!("Failed to remove installation directory: {e}"),
-            })?;
+pub fn remove_installation(app: AppHandle, id: u64) -> Result<String, UiError> {
+    let installation = store::find(&app, id)?;
+    // Remove the installation directory
+    let pb = PathBuf::from(&installation.path);
+    if pb.exists() && pb.is_dir() {
+        std::fs::remove_dir_all(&pb)
+            .map_err(|e| UiError::io("Failed to remove installation directory", e))?;
+    }
+    Ok("removed".into())
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CloneInstallationParams {
+    pub installation_id: u64,
+    pub destpath: String,
+    pub name: String,
+    pub emitevent: String,
+    // What to take along, everything but the worlds by default
+    pub mods: Option<bool>,
+    pub configs: Option<bool>,
+    pub settings: Option<bool>,
+    pub saves: Option<bool>,
+}
+
+// Keys play_game writes for the logged in account, a clone must not carry them around
+const SESSION_KEYS: [&str; 2] = ["sessionkey", "sessionsignature"];
+
+fn clone_settings(from: &Path, to: &Path) -> Result<(), UiError> {
+    let source = from.join("clientsettings.json");
+    if !source.is_file() {
+        return Ok(());
+    }
+    let contents = std::fs::read_to_string(&source)
+        .map_err(|e| UiError::io("Failed to read clientsettings.json", e))?;
+    let mut settings: Value = serde_json::from_str(&contents)
+        .map_err(|e| UiError::parse("Failed to parse clientsettings.json", e))?;
+    if let Some(string_settings) = settings
+        .get_mut("stringSettings")
+        .and_then(|v| v.as_object_mut())
+    {
+        for key in SESSION_KEYS {
+            string_settings.remove(key);
         }
-        Ok("removed".into())
-    } else {
-        Err(UiError {
-            name: "not_found".into(),
-            message: format!("Installation with id {} not found", id),
+    }
+    std::fs::write(
+        to.join("clientsettings.json"),
+        serde_json::to_string_pretty(&settings).unwrap(),
+    )
+    .map_err(|e| UiError::io("Failed to write clientsettings.json", e))
+}
+
+// Copies `files` from one installation directory to the same place in another
+fn copy_files(
+    app: &AppHandle,
+    from: &Path,
+    to: &Path,
+    files: &[PathBuf],
+    emitevent: &str,
+) -> Result<(), UiError> {
+    std::fs::create_dir_all(to.join("Mods"))
+        .map_err(|e| UiError::io("Failed to create directory", e))?;
+    let count = files.len() as u64;
+    for (i, file) in files.iter().enumerate() {
+        let relative = file.strip_prefix(from).unwrap_or(file);
+        let target = to.join(relative);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| UiError::io("Failed to create directory", e))?;
+        }
+        std::fs::copy(file, &target).map_err(|e| UiError::io("Failed to copy file", e))?;
+        app.emit(
+            emitevent,
+            ProgressPayload::step(
+                "clone",
+                i as u64 + 1,
+                count,
+                relative.to_string_lossy().into_owned(),
+            ),
+        )
+        .ok();
+    }
+    Ok(())
+}
+
+#[command]
+pub async fn clone_installation(
+    app: AppHandle,
+    options: CloneInstallationParams,
+) -> Result<Installation, UiError> {
+    let source = store::find(&app, options.installation_id)?;
+    let from = PathBuf::from(&source.path);
+    let to = PathBuf::from(&options.destpath);
+    let existing = store::all(&app)?;
+    let installation = Installation {
+        id: util::now_millis(),
+        name: options.name,
+        path: to.to_string_lossy().into_owned(),
+        version: source.version,
+        index: existing.iter().map(|i| i.index + 1).max().unwrap_or(0),
+        start_params: source.start_params,
+        icon: source.icon,
+        favorite: false,
+        last_time_played: 0,
+        total_time_played: 0,
+        extra: Map::new(),
+    };
+    store::ensure_unique(&app, &installation)?;
+    let occupied = std::fs::read_dir(&to)
+        .map(|mut entries| entries.next().is_some())
+        .unwrap_or(false);
+    if occupied {
+        return Err(UiError::new(
+            ErrorCode::InvalidState,
+            format!("{} is not empty", to.to_string_lossy()),
+        ));
+    }
+
+    let mut folders = vec![];
+    if options.mods.unwrap_or(true) {
+        folders.extend(["Mods", mods::DISABLED_DIR]);
+    }
+    if options.configs.unwrap_or(true) {
+        folders.push("ModConfig");
+    }
+    if options.saves.unwrap_or(false) {
+        folders.push("Saves");
+    }
+    let mut files = vec![];
+    for folder in folders {
+        let dir = from.join(folder);
+        if !dir.is_dir() {
+            continue;
+        }
+        for entry in walkdir::WalkDir::new(&dir) {
+            let entry = entry.map_err(|e| UiError::io("walkdir error", e))?;
+            if entry.file_type().is_file() {
+                files.push(entry.into_path());
+            }
+        }
+    }
+
+    let existed = to.exists();
+    let cloned = copy_files(&app, &from, &to, &files, &options.emitevent)
+        .and_then(|()| {
+            if options.settings.unwrap_or(true) {
+                clone_settings(&from, &to)?;
+            }
+            Ok(())
         })
+        .and_then(|()| store::insert(&app, &installation));
+    if let Err(e) = cloned {
+        util::discard_dir(&to, existed);
+        return Err(e);
     }
+    Ok(installation)
 }
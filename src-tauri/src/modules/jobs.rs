@@ -0,0 +1,335 @@
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+use tauri::{command, AppHandle, Emitter, Manager, Runtime, State};
+use tokio::sync::{watch, Semaphore};
+
+use super::download::{self, DownloadRequest, ProgressPayload};
+use super::errors::{ErrorCode, UiError};
+
+// How many jobs may transfer at the same time, the rest wait in the queue
+const MAX_CONCURRENT_DOWNLOADS: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobSignal {
+    Run,
+    Pause,
+    Cancel,
+}
+
+// Handed to download::run so a job can be interrupted and its .part file found again
+#[derive(Clone)]
+pub struct JobControl {
+    signal: watch::Receiver<JobSignal>,
+    partpath: Arc<Mutex<Option<PathBuf>>>,
+}
+
+impl JobControl {
+    pub fn signal(&self) -> &watch::Receiver<JobSignal> {
+        &self.signal
+    }
+
+    pub fn track_partial(&self, path: &Path) {
+        *self.partpath.lock().unwrap() = Some(path.to_path_buf());
+    }
+
+    fn remove_partial(&self) {
+        if let Some(path) = self.partpath.lock().unwrap().take() {
+            fs::remove_file(path).ok();
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobState {
+    Queued,
+    Running,
+    Paused,
+    Failed,
+    Done,
+    Cancelled,
+}
+
+impl JobState {
+    fn as_str(&self) -> &'static str {
+        match self {
+            JobState::Queued => "queued",
+            JobState::Running => "running",
+            JobState::Paused => "paused",
+            JobState::Failed => "failed",
+            JobState::Done => "done",
+            JobState::Cancelled => "cancelled",
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            JobState::Failed | JobState::Done | JobState::Cancelled
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadJob {
+    pub id: u64,
+    pub url: String,
+    pub destpath: String,
+    pub emitevent: String,
+    pub state: JobState,
+    pub error: Option<String>,
+}
+
+struct JobEntry {
+    job: DownloadJob,
+    request: DownloadRequest,
+    signal: watch::Sender<JobSignal>,
+    partpath: Arc<Mutex<Option<PathBuf>>>,
+}
+
+pub struct DownloadManager {
+    next_id: AtomicU64,
+    jobs: Mutex<HashMap<u64, JobEntry>>,
+    slots: Arc<Semaphore>,
+}
+
+impl Default for DownloadManager {
+    fn default() -> Self {
+        DownloadManager {
+            next_id: AtomicU64::new(1),
+            jobs: Mutex::new(HashMap::new()),
+            slots: Arc::new(Semaphore::new(MAX_CONCURRENT_DOWNLOADS)),
+        }
+    }
+}
+
+impl DownloadManager {
+    fn job(&self, id: u64) -> Result<DownloadJob, UiError> {
+        self.jobs
+            .lock()
+            .unwrap()
+            .get(&id)
+            .map(|entry| entry.job.clone())
+            .ok_or_else(|| UiError::not_found(format!("Download job {id} not found")))
+    }
+
+    fn signal(&self, id: u64, signal: JobSignal) -> Result<(), UiError> {
+        let jobs = self.jobs.lock().unwrap();
+        let entry = jobs
+            .get(&id)
+            .ok_or_else(|| UiError::not_found(format!("Download job {id} not found")))?;
+        entry.signal.send_replace(signal);
+        Ok(())
+    }
+
+    fn subscribe(&self, id: u64) -> Option<(DownloadRequest, JobControl)> {
+        self.jobs.lock().unwrap().get(&id).map(|entry| {
+            let control = JobControl {
+                signal: entry.signal.subscribe(),
+                partpath: entry.partpath.clone(),
+            };
+            (entry.request.clone(), control)
+        })
+    }
+
+    // Moves a failed or cancelled job back into the queue and tells whether its task has to
+    // be started again. Decided under the lock, so two quick resumes can't both start one
+    fn requeue(&self, id: u64) -> Result<bool, UiError> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let entry = jobs
+            .get_mut(&id)
+            .ok_or_else(|| UiError::not_found(format!("Download job {id} not found")))?;
+        let restart = match entry.job.state {
+            JobState::Done => {
+                return Err(UiError::new(
+                    ErrorCode::InvalidState,
+                    format!("Download job {id} has already finished"),
+                ))
+            }
+            JobState::Failed | JobState::Cancelled => {
+                entry.job.state = JobState::Queued;
+                entry.job.error = None;
+                true
+            }
+            _ => false,
+        };
+        entry.signal.send_replace(JobSignal::Run);
+        Ok(restart)
+    }
+
+    // Updates the job and reports the transition on the job's own event channel
+    fn transition<R: Runtime>(
+        &self,
+        app: &AppHandle<R>,
+        id: u64,
+        state: JobState,
+        error: Option<String>,
+    ) {
+        let emitevent = {
+            let mut jobs = self.jobs.lock().unwrap();
+            let Some(entry) = jobs.get_mut(&id) else {
+                return;
+            };
+            entry.job.state = state;
+            entry.job.error = error.clone();
+            entry.job.emitevent.clone()
+        };
+        app.emit(&emitevent, ProgressPayload::phase(state.as_str(), error))
+            .ok();
+    }
+}
+
+async fn run_job<R: Runtime>(app: AppHandle<R>, id: u64) {
+    let manager = app.state::<DownloadManager>();
+    let Some((request, control)) = manager.subscribe(id) else {
+        return;
+    };
+    let mut signal = control.signal().clone();
+
+    loop {
+        // Paused jobs sit here until they are resumed or cancelled
+        let cancelled = match signal.wait_for(|s| *s != JobSignal::Pause).await {
+            Ok(s) => *s == JobSignal::Cancel,
+            Err(_) => true,
+        };
+        if cancelled {
+            control.remove_partial();
+            manager.transition(&app, id, JobState::Cancelled, None);
+            return;
+        }
+
+        manager.transition(&app, id, JobState::Queued, None);
+        let permit = tokio::select! {
+            permit = manager.slots.clone().acquire_owned() => match permit {
+                Ok(permit) => permit,
+                Err(_) => return,
+            },
+            s = signal.wait_for(|s| *s != JobSignal::Run) => {
+                if s.map(|s| *s == JobSignal::Pause).unwrap_or(false) {
+                    manager.transition(&app, id, JobState::Paused, None);
+                }
+                continue;
+            }
+        };
+
+        manager.transition(&app, id, JobState::Running, None);
+        let result = download::run(&app, request.clone(), Some(&control)).await;
+        drop(permit);
+
+        // A transfer that finished is done, even when it was paused or cancelled too late
+        let current = *signal.borrow();
+        match (current, result) {
+            (_, Ok(_)) => {
+                manager.transition(&app, id, JobState::Done, None);
+                return;
+            }
+            (JobSignal::Cancel, _) => {
+                control.remove_partial();
+                manager.transition(&app, id, JobState::Cancelled, None);
+                return;
+            }
+            (JobSignal::Pause, _) => {
+                manager.transition(&app, id, JobState::Paused, None);
+            }
+            (JobSignal::Run, Err(e)) => {
+                manager.transition(&app, id, JobState::Failed, Some(e.message));
+                return;
+            }
+        }
+    }
+}
+
+#[command]
+pub fn enqueue_download<R: Runtime>(
+    app: AppHandle<R>,
+    manager: State<'_, DownloadManager>,
+    request: DownloadRequest,
+) -> Result<DownloadJob, UiError> {
+    let id = manager.next_id.fetch_add(1, Ordering::Relaxed);
+    let job = DownloadJob {
+        id,
+        url: request.url.clone(),
+        destpath: request.destpath.clone(),
+        emitevent: request.emitevent.clone(),
+        state: JobState::Queued,
+        error: None,
+    };
+    let (signal, _) = watch::channel(JobSignal::Run);
+    manager.jobs.lock().unwrap().insert(
+        id,
+        JobEntry {
+            job: job.clone(),
+            request,
+            signal,
+            partpath: Arc::new(Mutex::new(None)),
+        },
+    );
+    tauri::async_runtime::spawn(run_job(app, id));
+    Ok(job)
+}
+
+#[command]
+pub fn list_downloads(manager: State<'_, DownloadManager>) -> Vec<DownloadJob> {
+    let mut jobs: Vec<DownloadJob> = manager
+        .jobs
+        .lock()
+        .unwrap()
+        .values()
+        .map(|entry| entry.job.clone())
+        .collect();
+    jobs.sort_by_key(|job| job.id);
+    jobs
+}
+
+#[command]
+pub fn pause_download(manager: State<'_, DownloadManager>, id: u64) -> Result<(), UiError> {
+    let job = manager.job(id)?;
+    if job.state.is_finished() {
+        return Err(UiError::new(
+            ErrorCode::InvalidState,
+            format!("Download job {id} has already finished"),
+        ));
+    }
+    manager.signal(id, JobSignal::Pause)
+}
+
+#[command]
+pub fn resume_download<R: Runtime>(
+    app: AppHandle<R>,
+    manager: State<'_, DownloadManager>,
+    id: u64,
+) -> Result<(), UiError> {
+    // Failed and cancelled jobs are retried from whatever is left on disk
+    if manager.requeue(id)? {
+        tauri::async_runtime::spawn(run_job(app, id));
+    }
+    Ok(())
+}
+
+#[command]
+pub fn cancel_download(manager: State<'_, DownloadManager>, id: u64) -> Result<(), UiError> {
+    let job = manager.job(id)?;
+    if job.state.is_finished() {
+        // Nothing is running anymore, just forget about it
+        manager.jobs.lock().unwrap().remove(&id);
+        return Ok(());
+    }
+    manager.signal(id, JobSignal::Cancel)
+}
+
+#[command]
+pub fn clear_finished_downloads(manager: State<'_, DownloadManager>) {
+    manager
+        .jobs
+        .lock()
+        .unwrap()
+        .retain(|_, entry| !entry.job.state.is_finished());
+}
@@ -0,0 +1,535 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::{
+    fs::{self, File},
+    io::{self, Read, Seek, Write},
+    path::{Path, PathBuf},
+};
+use tauri::{command, AppHandle, Emitter, Manager};
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+use super::download::{self, DownloadRequest, ProgressPayload};
+use super::errors::{ErrorCode, UiError};
+use super::mods;
+use super::store::{self, Installation};
+use super::util::{self, now_millis, zip_error};
+
+pub const MANIFEST_VERSION: u32 = 1;
+// Entry names inside a bundled modpack zip
+pub const MANIFEST_NAME: &str = "modpack.json";
+pub const MODS_FOLDER: &str = "mods/";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModpackMod {
+    pub modid: String,
+    pub name: String,
+    pub version: String,
+    pub filename: String,
+    // ModDB release the file came from, if it could be found
+    #[serde(default)]
+    pub releaseid: Option<i64>,
+    // "sha256:<hex>"
+    pub hash: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModpackConfig {
+    // Relative to ModConfig/, always with forward slashes
+    pub path: String,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModpackManifest {
+    pub format_version: u32,
+    pub name: String,
+    pub game_version: String,
+    #[serde(default)]
+    pub start_params: String,
+    // Milliseconds since the epoch
+    #[serde(default)]
+    pub created_at: u64,
+    pub mods: Vec<ModpackMod>,
+    #[serde(default)]
+    pub configs: Vec<ModpackConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExportModpackParams {
+    pub installation_id: u64,
+    // Where to write the manifest, or the zip when bundling mods
+    pub destpath: String,
+    pub name: Option<String>,
+    #[serde(default)]
+    pub include_configs: bool,
+    #[serde(default)]
+    pub bundle_mods: bool,
+    // Look up ModDB release ids, needs one request per mod
+    pub resolve_releases: Option<bool>,
+}
+
+pub fn sha256_file(path: &Path) -> Result<String, UiError> {
+    let mut file = File::open(path).map_err(|e| UiError::io("open file error", e))?;
+    sha256_reader(&mut file)
+}
+
+fn sha256_reader(reader: &mut impl Read) -> Result<String, UiError> {
+    let mut hasher = Sha256::new();
+    io::copy(reader, &mut hasher).map_err(|e| UiError::io("read file error", e))?;
+    let hex: String = hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    Ok(format!("sha256:{hex}"))
+}
+
+async fn find_release_id(
+    app: &AppHandle,
+    modid: &str,
+    version: &str,
+    filename: &str,
+) -> Option<i64> {
+    let info = mods::fetch_mod_info(app.clone(), modid.to_string())
+        .await
+        .ok()?;
+    info.details
+        .releases
+        .iter()
+        .find(|r| r.filename == filename || r.modversion == version)
+        .map(|r| r.releaseid)
+}
+
+fn collect_configs(installation_path: &str) -> Result<Vec<ModpackConfig>, UiError> {
+    let root = Path::new(installation_path).join("ModConfig");
+    if !root.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut configs = vec![];
+    for entry in walkdir::WalkDir::new(&root).sort_by_file_name() {
+        let entry = entry.map_err(|e| UiError::io("walkdir error", e))?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let bytes = fs::read(entry.path()).map_err(|e| UiError::io("Read file error", e))?;
+        // Configs are text, anything else is not worth carrying around
+        let Ok(content) = String::from_utf8(bytes) else {
+            continue;
+        };
+        let path = entry
+            .path()
+            .strip_prefix(&root)
+            .unwrap_or(entry.path())
+            .to_string_lossy()
+            .replace('\\', "/");
+        configs.push(ModpackConfig { path, content });
+    }
+    Ok(configs)
+}
+
+fn write_bundle(
+    destpath: &Path,
+    manifest: &ModpackManifest,
+    files: &[PathBuf],
+) -> Result<(), UiError> {
+    let file = File::create(destpath).map_err(|e| UiError::io("Failed to create modpack", e))?;
+    let mut zip = ZipWriter::new(file);
+    let json = serde_json::to_string_pretty(manifest)?;
+    zip.start_file(MANIFEST_NAME, SimpleFileOptions::default())
+        .map_err(|e| zip_error("Failed to write modpack", e))?;
+    zip.write_all(json.as_bytes())
+        .map_err(|e| UiError::io("Failed to write modpack", e))?;
+    // Mods are zips already, compressing them again gains nothing
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    for (path, m) in files.iter().zip(&manifest.mods) {
+        zip.start_file(format!("{MODS_FOLDER}{}", m.filename), stored)
+            .map_err(|e| zip_error("Failed to write modpack", e))?;
+        let mut source = File::open(path).map_err(|e| UiError::io("Open file error", e))?;
+        io::copy(&mut source, &mut zip).map_err(|e| UiError::io("Failed to write modpack", e))?;
+    }
+    zip.finish()
+        .map_err(|e| zip_error("Failed to write modpack", e))?;
+    Ok(())
+}
+
+#[command]
+pub async fn export_modpack(
+    app: AppHandle,
+    options: ExportModpackParams,
+) -> Result<ModpackManifest, UiError> {
+    let installation = store::find(&app, options.installation_id)?;
+    let installed = mods::enabled_mods(installation.path.clone())?;
+    let resolve = options.resolve_releases.unwrap_or(true);
+
+    let mut files = vec![];
+    let mut packed = vec![];
+    for m in installed {
+        let path = PathBuf::from(&m.path);
+        let filename = path
+            .file_name()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default()
+            .to_string();
+        let size = fs::metadata(&path)
+            .map_err(|e| UiError::io("Failed to read mod file", e))?
+            .len();
+        let releaseid = if resolve {
+            find_release_id(&app, &m.modid, &m.version, &filename).await
+        } else {
+            None
+        };
+        packed.push(ModpackMod {
+            hash: sha256_file(&path)?,
+            modid: m.modid,
+            name: m.name,
+            version: m.version,
+            filename,
+            releaseid,
+            size,
+        });
+        files.push(path);
+    }
+
+    let manifest = ModpackManifest {
+        format_version: MANIFEST_VERSION,
+        name: options.name.unwrap_or_else(|| installation.name.clone()),
+        game_version: installation.version.clone(),
+        start_params: installation.start_params.clone(),
+        created_at: now_millis(),
+        mods: packed,
+        configs: if options.include_configs {
+            collect_configs(&installation.path)?
+        } else {
+            vec![]
+        },
+    };
+
+    let destpath = PathBuf::from(&options.destpath);
+    if let Some(parent) = destpath.parent() {
+        fs::create_dir_all(parent).map_err(|e| UiError::io("Failed to create directory", e))?;
+    }
+    if options.bundle_mods {
+        if let Err(e) = write_bundle(&destpath, &manifest, &files) {
+            fs::remove_file(&destpath).ok();
+            return Err(e);
+        }
+    } else {
+        let json = serde_json::to_string_pretty(&manifest)?;
+        fs::write(&destpath, json).map_err(|e| UiError::io("Failed to write manifest", e))?;
+    }
+    Ok(manifest)
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ImportModpackParams {
+    // A modpack.json or a bundled modpack zip
+    pub path: String,
+    // Directory of the new installation, must be empty or not exist yet
+    pub destpath: String,
+    pub name: Option<String>,
+    pub emitevent: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModpackFailure {
+    pub modid: String,
+    pub filename: String,
+    pub error: UiError,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportResult {
+    pub installation: Installation,
+    pub installed: Vec<String>,
+    pub failures: Vec<ModpackFailure>,
+    pub configs: usize,
+}
+
+fn is_zip(path: &Path) -> bool {
+    let mut magic = [0u8; 2];
+    File::open(path)
+        .and_then(|mut f| f.read_exact(&mut magic))
+        .is_ok()
+        && &magic == b"PK"
+}
+
+// Reads a manifest from either a bare modpack.json or a bundled zip
+pub fn read_manifest(path: &Path) -> Result<ModpackManifest, UiError> {
+    let json = if is_zip(path) {
+        let file = File::open(path).map_err(|e| UiError::io("Failed to open modpack", e))?;
+        let mut archive =
+            ZipArchive::new(file).map_err(|e| zip_error("Failed to read modpack", e))?;
+        let mut entry = archive
+            .by_name(MANIFEST_NAME)
+            .map_err(|e| zip_error("Modpack has no manifest", e))?;
+        let mut json = String::new();
+        entry
+            .read_to_string(&mut json)
+            .map_err(|e| UiError::io("Failed to read manifest", e))?;
+        json
+    } else {
+        fs::read_to_string(path).map_err(|e| UiError::io("Failed to read manifest", e))?
+    };
+    let manifest: ModpackManifest =
+        serde_json::from_str(&json).map_err(|e| UiError::parse("Invalid modpack manifest", e))?;
+    if manifest.format_version > MANIFEST_VERSION {
+        return Err(UiError::new(
+            ErrorCode::Unsupported,
+            format!(
+                "Modpack format {} is newer than this launcher supports",
+                manifest.format_version
+            ),
+        ));
+    }
+    Ok(manifest)
+}
+
+// Same folder prefixes the frontend passes for game archives, see zipfolderprefix() in utils.ts
+fn game_archive_prefix() -> &'static str {
+    match tauri_plugin_os::platform() {
+        "windows" => "app/",
+        "linux" => "AppImage/",
+        "macos" => "*.app/",
+        _ => "",
+    }
+}
+
+async fn ensure_game_version(
+    app: &AppHandle,
+    version: &str,
+    emitevent: &str,
+) -> Result<(), UiError> {
+    let version_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| UiError::io("Failed to resolve app data dir", e))?
+        .join("versions")
+        .join(version);
+    if version_dir.is_dir() {
+        return Ok(());
+    }
+    let info = download::get_download_info(app.clone(), version).await?;
+    let version_dir = version_dir.to_string_lossy().into_owned();
+    download::run(
+        app,
+        DownloadRequest {
+            url: info.url,
+            destpath: version_dir.clone(),
+            emitevent: emitevent.to_string(),
+            extract: true,
+            extractdir: Some(version_dir),
+            zipsubfolderprefix: Some(game_archive_prefix().to_string()),
+            expectedsize: info.filesize,
+            expectedhash: info.hash,
+            stripcomponents: None,
+            filename: None,
+        },
+        None,
+    )
+    .await?;
+    Ok(())
+}
+
+// Copies a mod out of a bundled modpack, checking it against the manifest hash
+fn unbundle_mod(bundle: &Path, m: &ModpackMod, mods_dir: &Path) -> Result<bool, UiError> {
+    let file = File::open(bundle).map_err(|e| UiError::io("Failed to open modpack", e))?;
+    let mut archive = ZipArchive::new(file).map_err(|e| zip_error("Failed to read modpack", e))?;
+    let Ok(mut entry) = archive.by_name(&format!("{MODS_FOLDER}{}", m.filename)) else {
+        return Ok(false);
+    };
+    let target = download::make_output_path(mods_dir, &m.filename, "")?;
+    util::write_atomic_with(&target, |out| {
+        io::copy(&mut entry, out).map_err(|e| UiError::io("Failed to write file", e))?;
+        out.rewind()
+            .map_err(|e| UiError::io("Failed to write file", e))?;
+        let actual = sha256_reader(out)?;
+        if !actual.eq_ignore_ascii_case(&m.hash) {
+            return Err(UiError::new(
+                ErrorCode::Verification,
+                format!("{} does not match the manifest hash", m.filename),
+            )
+            .with_details(serde_json::json!({ "expected": m.hash, "actual": actual })));
+        }
+        Ok(())
+    })?;
+    Ok(true)
+}
+
+// Finds the ModDB download for a manifest entry, by release id or else by version
+async fn release_url(app: &AppHandle, m: &ModpackMod) -> Result<String, UiError> {
+    let info = mods::fetch_mod_info(app.clone(), m.modid.clone()).await?;
+    let releases = &info.details.releases;
+    releases
+        .iter()
+        .find(|r| m.releaseid == Some(r.releaseid))
+        .or_else(|| releases.iter().find(|r| r.modversion == m.version))
+        .map(|r| r.mainfile.clone())
+        .filter(|url| !url.is_empty())
+        .ok_or_else(|| {
+            UiError::not_found(format!(
+                "No ModDB release of {} {} found",
+                m.modid, m.version
+            ))
+        })
+}
+
+async fn install_mod(
+    app: &AppHandle,
+    bundle: Option<&Path>,
+    m: &ModpackMod,
+    mods_dir: &Path,
+    emitevent: &str,
+) -> Result<(), UiError> {
+    if let Some(bundle) = bundle {
+        if unbundle_mod(bundle, m, mods_dir)? {
+            return Ok(());
+        }
+    }
+    let url = release_url(app, m).await?;
+    download::run(
+        app,
+        DownloadRequest {
+            url,
+            destpath: mods_dir.to_string_lossy().into_owned(),
+            emitevent: emitevent.to_string(),
+            extract: false,
+            extractdir: None,
+            zipsubfolderprefix: None,
+            expectedsize: Some(m.size),
+            expectedhash: Some(m.hash.clone()),
+            stripcomponents: None,
+            filename: Some(m.filename.clone()),
+        },
+        None,
+    )
+    .await?;
+    Ok(())
+}
+
+fn restore_configs(installation_path: &Path, configs: &[ModpackConfig]) -> Result<(), UiError> {
+    let root = installation_path.join("ModConfig");
+    fs::create_dir_all(&root).map_err(|e| UiError::io("Failed to create directory", e))?;
+    for config in configs {
+        let target = download::make_output_path(&root, &config.path, "")?;
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| UiError::io("Failed to create directory", e))?;
+        }
+        fs::write(&target, &config.content)
+            .map_err(|e| UiError::io("Failed to write config", e))?;
+    }
+    Ok(())
+}
+
+fn emit_import(app: &AppHandle, emitevent: &str, current: u64, count: u64, message: String) {
+    app.emit(
+        emitevent,
+        ProgressPayload::step("import", current, count, message),
+    )
+    .ok();
+}
+
+#[command]
+pub fn read_modpack(path: String) -> Result<ModpackManifest, UiError> {
+    read_manifest(Path::new(&path))
+}
+
+// Fills `destpath` with the game version, the mods and their configs; failed mods are
+// reported instead of failing the import
+async fn import_files(
+    app: &AppHandle,
+    manifest: &ModpackManifest,
+    bundle: Option<&Path>,
+    destpath: &Path,
+    emitevent: &str,
+) -> Result<(Vec<String>, Vec<ModpackFailure>), UiError> {
+    // Steps: the game, every mod, then the configs
+    let count = manifest.mods.len() as u64 + 2;
+    emit_import(
+        app,
+        emitevent,
+        0,
+        count,
+        format!("Game {}", manifest.game_version),
+    );
+    ensure_game_version(app, &manifest.game_version, emitevent).await?;
+
+    let mods_dir = destpath.join("Mods");
+    fs::create_dir_all(&mods_dir).map_err(|e| UiError::io("Failed to create directory", e))?;
+    let mut installed = vec![];
+    let mut failures = vec![];
+    for (i, m) in manifest.mods.iter().enumerate() {
+        emit_import(app, emitevent, i as u64 + 1, count, m.name.clone());
+        match install_mod(app, bundle, m, &mods_dir, emitevent).await {
+            Ok(()) => installed.push(m.modid.clone()),
+            Err(error) => failures.push(ModpackFailure {
+                modid: m.modid.clone(),
+                filename: m.filename.clone(),
+                error,
+            }),
+        }
+    }
+
+    emit_import(app, emitevent, count - 1, count, "Mod configs".into());
+    restore_configs(destpath, &manifest.configs)?;
+    Ok((installed, failures))
+}
+
+#[command]
+pub async fn import_modpack(
+    app: AppHandle,
+    options: ImportModpackParams,
+) -> Result<ImportResult, UiError> {
+    let source = PathBuf::from(&options.path);
+    let manifest = read_manifest(&source)?;
+    let bundle = is_zip(&source).then_some(source.as_path());
+
+    let destpath = PathBuf::from(&options.destpath);
+    let existing = store::all(&app)?;
+    let installation = Installation {
+        id: now_millis(),
+        name: options.name.unwrap_or_else(|| manifest.name.clone()),
+        path: destpath.to_string_lossy().into_owned(),
+        version: manifest.game_version.clone(),
+        index: existing.iter().map(|i| i.index + 1).max().unwrap_or(0),
+        start_params: manifest.start_params.clone(),
+        icon: None,
+        favorite: false,
+        last_time_played: 0,
+        total_time_played: 0,
+        extra: Map::<String, Value>::new(),
+    };
+    // Before anything is downloaded, a clash would throw all of it away
+    store::ensure_unique(&app, &installation)?;
+    let occupied = fs::read_dir(&destpath)
+        .map(|mut entries| entries.next().is_some())
+        .unwrap_or(false);
+    if occupied {
+        return Err(UiError::new(
+            ErrorCode::InvalidState,
+            format!("{} is not empty", destpath.to_string_lossy()),
+        ));
+    }
+
+    let existed = destpath.exists();
+    let imported = import_files(&app, &manifest, bundle, &destpath, &options.emitevent)
+        .await
+        .and_then(|files| store::insert(&app, &installation).map(|()| files));
+    let (installed, failures) = match imported {
+        Ok(files) => files,
+        Err(e) => {
+            util::discard_dir(&destpath, existed);
+            return Err(e);
+        }
+    };
+    let count = manifest.mods.len() as u64 + 2;
+    emit_import(&app, &options.emitevent, count, count, "Done".into());
+
+    Ok(ImportResult {
+        installation,
+        installed,
+        failures,
+        configs: manifest.configs.len(),
+    })
+}
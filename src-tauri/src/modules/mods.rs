@@ -1,20 +1,33 @@
-use reqwest::header::{HeaderMap, HeaderValue, HOST};
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{json, Value};
 use std::{
+    cmp::Ordering,
+    collections::BTreeMap,
     fs::File,
     io::{Read, Write},
     path::{Path, PathBuf},
     str::FromStr,
 };
 use tauri::{command, AppHandle};
-use tauri_plugin_zustand::ManagerExt;
 use zip::read::ZipArchive;
 
-use super::errors::UiError;
+use super::cache::CachePolicy;
+use super::dependencies::compare_versions;
+use super::download::{self, DownloadRequest};
+use super::errors::{ErrorCode, UiError};
+use super::http::{self, Service};
+use super::store;
 
-#[allow(dead_code)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// Disabled mods are parked here, next to Mods/ so the game doesn't load them
+pub const DISABLED_DIR: &str = "Mods.disabled";
+
+const TAGS_CACHE: CachePolicy = CachePolicy::minutes(24 * 60, 7 * 24 * 60);
+const MODS_CACHE: CachePolicy = CachePolicy::minutes(10, 24 * 60);
+// Short, installs and updates pick releases from this
+const MOD_INFO_CACHE: CachePolicy = CachePolicy::minutes(5, 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
 pub enum ModSortBy {
     Created,
     LastReleased,
@@ -40,8 +53,30 @@ impl FromStr for ModSortBy {
     }
 }
 
-#[allow(dead_code)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+impl TryFrom<String> for ModSortBy {
+    type Error = &'static str;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl ModSortBy {
+    // The `orderby` value of the ModDB api
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ModSortBy::Created => "asset.created",
+            ModSortBy::LastReleased => "lastreleased",
+            ModSortBy::Downloads => "downloads",
+            ModSortBy::Follows => "follows",
+            ModSortBy::Comments => "comments",
+            ModSortBy::TrendingPoints => "trendingpoints",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
 pub enum ModSortOrder {
     Desc,
     Asc,
@@ -59,6 +94,61 @@ impl FromStr for ModSortOrder {
     }
 }
 
+impl TryFrom<String> for ModSortOrder {
+    type Error = &'static str;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl ModSortOrder {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ModSortOrder::Desc => "desc",
+            ModSortOrder::Asc => "asc",
+        }
+    }
+}
+
+// Values of `Mod::side`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModSide {
+    Client,
+    Server,
+    Both,
+}
+
+impl ModSide {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ModSide::Client => "client",
+            ModSide::Server => "server",
+            ModSide::Both => "both",
+        }
+    }
+}
+
+// Values of `Mod::type`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModType {
+    Mod,
+    ExternalTool,
+    Other,
+}
+
+impl ModType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ModType::Mod => "mod",
+            ModType::ExternalTool => "externaltool",
+            ModType::Other => "other",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct ModRemoveParams {
     pub path: String,
@@ -66,9 +156,35 @@ pub struct ModRemoveParams {
 }
 
 #[derive(Debug, Clone, Deserialize)]
+pub struct InstallModParams {
+    pub installation_id: u64,
+    // Numeric ModDB id or the mod's string id, e.g. "carryon"
+    pub modid: String,
+    pub emitevent: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InstalledRelease {
+    pub modid: String,
+    pub name: String,
+    pub release: ModRelease,
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
 pub struct FetchModsParams {
     pub versions: Vec<String>,
     pub search: String,
+    pub tagids: Vec<i64>,
+    // ModDB user id, as returned by fetch_authors
+    pub author: Option<i64>,
+    pub side: Option<ModSide>,
+    pub modtype: Option<ModType>,
+    pub sortby: Option<ModSortBy>,
+    pub sortorder: Option<ModSortOrder>,
+    pub offset: usize,
+    pub limit: Option<usize>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -97,6 +213,15 @@ pub struct ModsResponse {
     mods: Vec<Mod>,
 }
 
+// One page of search results; `total` counts all matches, not just this page
+#[derive(Debug, Serialize)]
+pub struct ModSearchResult {
+    pub mods: Vec<Mod>,
+    pub total: usize,
+    pub offset: usize,
+    pub limit: Option<usize>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ModTags {
     tagid: i64,
@@ -110,6 +235,130 @@ pub struct ModTagsResponse {
     tags: Vec<ModTags>,
 }
 
+// ModDB sends null for some missing values, one such release must not fail the whole mod
+fn null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Option::unwrap_or_default)
+}
+
+// A release as returned by the ModDB api, e.g. in /api/updates. The download url in
+// `mainfile` is empty when the release has no file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModRelease {
+    pub releaseid: i64,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub mainfile: String,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub filename: String,
+    #[serde(default)]
+    pub fileid: Option<i64>,
+    #[serde(default)]
+    pub downloads: i64,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub tags: Vec<String>,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub modidstr: String,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub modversion: String,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub created: String,
+    #[serde(default)]
+    pub changelog: Option<String>,
+}
+
+impl ModRelease {
+    // Release tags are the game versions the author marked the release for
+    pub fn tagged_for(&self, game_version: &str) -> bool {
+        self.tags
+            .iter()
+            .any(|tag| compare_versions(tag, game_version) == Ordering::Equal)
+    }
+
+    // Same major.minor as the game; mods rarely break between patch releases
+    fn compatible_with(&self, game_version: &str) -> bool {
+        let minor = |v: &str| v.split('.').take(2).collect::<Vec<_>>().join(".");
+        let wanted = minor(game_version.trim_start_matches('v'));
+        self.tags
+            .iter()
+            .any(|tag| minor(tag.trim_start_matches('v')) == wanted)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModScreenshot {
+    pub fileid: Option<i64>,
+    pub mainfile: String,
+    pub filename: String,
+    pub thumbnailfilename: Option<String>,
+    pub created: String,
+}
+
+// The `mod` object of /api/mod/{id}
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModDetails {
+    pub modid: i64,
+    pub assetid: i64,
+    pub name: String,
+    pub text: String,
+    pub author: String,
+    pub urlalias: Option<String>,
+    pub logofilename: Option<String>,
+    pub logofile: Option<String>,
+    pub logofiledb: Option<String>,
+    pub homepageurl: Option<String>,
+    pub sourcecodeurl: Option<String>,
+    pub trailervideourl: Option<String>,
+    pub issuetrackerurl: Option<String>,
+    pub wikiurl: Option<String>,
+    pub downloads: i64,
+    pub follows: i64,
+    pub trendingpoints: i64,
+    pub comments: i64,
+    pub side: String,
+    pub r#type: String,
+    pub created: String,
+    pub lastreleased: String,
+    pub lastmodified: String,
+    pub tags: Vec<String>,
+    pub releases: Vec<ModRelease>,
+    pub screenshots: Vec<ModScreenshot>,
+}
+
+// Mirrors the `ModInfo` type in src/lib/types.ts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModInfo {
+    pub statuscode: String,
+    #[serde(rename = "mod")]
+    pub details: ModDetails,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModInfoResponse {
+    statuscode: String,
+    #[serde(rename = "mod")]
+    details: Option<ModDetails>,
+}
+
+// The newest release tagged for exactly this game version, else the newest one
+// tagged for the same minor version. Releases without a file are skipped
+pub fn best_release<'a>(releases: &'a [ModRelease], game_version: &str) -> Option<&'a ModRelease> {
+    let newest = |filter: &dyn Fn(&ModRelease) -> bool| {
+        releases
+            .iter()
+            .filter(|r| !r.mainfile.is_empty() && filter(r))
+            .max_by(|a, b| {
+                compare_versions(&a.modversion, &b.modversion)
+                    .then_with(|| a.created.cmp(&b.created))
+            })
+    };
+    newest(&|r| r.tagged_for(game_version)).or_else(|| newest(&|r| r.compatible_with(game_version)))
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OutputMod {
     pub modid: String,
@@ -117,6 +366,16 @@ pub struct OutputMod {
     pub authors: Vec<String>,
     pub version: String,
     pub path: String,
+    // modid -> minimum version, "*" or "" for any. Includes "game", "survival" and "creative"
+    #[serde(default)]
+    pub dependencies: BTreeMap<String, String>,
+    // False for mods parked in Mods.disabled/
+    #[serde(default = "enabled_default")]
+    pub enabled: bool,
+}
+
+fn enabled_default() -> bool {
+    true
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -133,32 +392,27 @@ pub struct ModError {
 }
 
 #[command]
-pub async fn fetch_mod_tags() -> Result<Vec<ModTags>, UiError> {
-    let res = reqwest::get("https://mods.vintagestory.at/api/tags")
-        .await
-        .map_err(|e| format!("Request error: {e}"))?;
+pub async fn fetch_mod_tags(app: AppHandle) -> Result<Vec<ModTags>, UiError> {
+    let api = http::api(&app);
+    let request = api.client().get(api.url(Service::ModDb, "/api/tags"));
+    let body = http::cached_text(&app, &api, request, TAGS_CACHE).await?;
 
-    if !res.status().is_success() {
-        return Err(UiError {
-            name: "http_error".into(),
-            message: format!("HTTP error: {}", res.status()),
-        });
-    }
-
-    let json = res
-        .json::<ModTagsResponse>()
-        .await
-        .map_err(|e| format!("JSON error: {e}"))?;
+    let json: ModTagsResponse =
+        serde_json::from_str(&body).map_err(|e| UiError::parse("JSON parse error", e))?;
 
     Ok(json.tags)
 }
 
+// Searches ModDB. Versions, text, tags, author and sorting are sent to the api. It has
+// no side, type or paging parameters, so those filter and slice the full (cached) result
+// here: `offset` and `limit` shorten the reply to the UI, not the download from ModDB.
 #[command]
-pub async fn fetch_mods(options: FetchModsParams) -> Result<Vec<Mod>, UiError> {
+pub async fn fetch_mods(
+    app: AppHandle,
+    options: FetchModsParams,
+) -> Result<ModSearchResult, UiError> {
     // Use the parameters for a GET request with search parameters
-    let client = reqwest::Client::new();
-    let mut headers = HeaderMap::new();
-    headers.insert(HOST, HeaderValue::from_static("mods.vintagestory.at"));
+    let api = http::api(&app);
 
     let mut params = Vec::new();
     if !options.versions.is_empty() {
@@ -169,109 +423,220 @@ pub async fn fetch_mods(options: FetchModsParams) -> Result<Vec<Mod>, UiError> {
     if !options.search.is_empty() {
         params.push(("text".to_string(), options.search.clone()));
     }
+    for tagid in &options.tagids {
+        params.push(("tagids[]".to_string(), tagid.to_string()));
+    }
+    if let Some(author) = options.author {
+        params.push(("author".to_string(), author.to_string()));
+    }
+    if let Some(sortby) = options.sortby {
+        params.push(("orderby".to_string(), sortby.as_str().to_string()));
+    }
+    if let Some(sortorder) = options.sortorder {
+        params.push(("orderdirection".to_string(), sortorder.as_str().to_string()));
+    }
 
-    let res = client
-        .get("https://mods.vintagestory.at/api/mods")
-        .headers(headers)
-        .query(&params)
-        .send()
-        .await
-        .map_err(|e| format!("Request error: {e}"))?;
+    let request = api
+        .client()
+        .get(api.url(Service::ModDb, "/api/mods"))
+        .query(&params);
+    let body = http::cached_text(&app, &api, request, MODS_CACHE).await?;
 
-    if !res.status().is_success() {
-        return Err(UiError {
-            name: "http_error".into(),
-            message: format!("HTTP error: {}", res.status()),
-        });
-    }
+    let json: ModsResponse =
+        serde_json::from_str(&body).map_err(|e| UiError::parse("JSON parse error", e))?;
 
-    let json = res
-        .json::<ModsResponse>()
-        .await
-        .map_err(|e| format!("JSON error: {e}"))?;
-    Ok(json.mods)
+    let matches: Vec<Mod> = json
+        .mods
+        .into_iter()
+        .filter(|m| options.side.is_none_or(|side| m.side == side.as_str()))
+        .filter(|m| options.modtype.is_none_or(|t| m.r#type == t.as_str()))
+        .collect();
+    let total = matches.len();
+    let mods = matches
+        .into_iter()
+        .skip(options.offset)
+        .take(options.limit.unwrap_or(usize::MAX))
+        .collect();
+    Ok(ModSearchResult {
+        mods,
+        total,
+        offset: options.offset,
+        limit: options.limit,
+    })
 }
 
 #[command]
-pub async fn fetch_mod_info(modid: String) -> Result<Value, UiError> {
-    let url = format!("https://mods.vintagestory.at/api/mod/{}", modid);
-    let res = reqwest::get(&url)
-        .await
-        .map_err(|e| UiError::from(format!("Request error: {e}")))?
-        .text()
-        .await
-        .map_err(|e| UiError::from(format!("Read error: {e}")))?;
+pub async fn fetch_mod_info(app: AppHandle, modid: String) -> Result<ModInfo, UiError> {
+    let api = http::api(&app);
+    let url = api.url(Service::ModDb, &format!("/api/mod/{}", modid));
+    let res = http::cached_text(&app, &api, api.client().get(url), MOD_INFO_CACHE).await?;
 
-    let json: serde_json::Value =
-        serde_json::from_str(&res).map_err(|e| UiError::from(format!("JSON parse error: {e}")))?;
-    Ok(json)
+    let json: ModInfoResponse =
+        serde_json::from_str(&res).map_err(|e| UiError::parse("JSON parse error", e))?;
+    let details = json
+        .details
+        .ok_or_else(|| UiError::not_found(format!("Mod {modid} not found on ModDB")))?;
+    Ok(ModInfo {
+        statuscode: json.statuscode,
+        details,
+    })
+}
+
+// The release to install for a game version, None when no release supports it
+#[command]
+pub async fn get_best_release(
+    app: AppHandle,
+    modid: String,
+    game_version: String,
+) -> Result<Option<ModRelease>, UiError> {
+    let info = fetch_mod_info(app, modid).await?;
+    Ok(best_release(&info.details.releases, &game_version).cloned())
 }
 
 #[command]
-pub async fn fetch_authors(search: String) -> Result<serde_json::Value, UiError> {
-    let url = format!(
-        "https://mods.vintagestory.at/api/v2/users/by-name/{}?contributors-only=true",
-        search
+pub async fn fetch_authors(app: AppHandle, search: String) -> Result<serde_json::Value, UiError> {
+    let api = http::api(&app);
+    let url = api.url(
+        Service::ModDb,
+        &format!("/api/v2/users/by-name/{}?contributors-only=true", search),
     );
-    let res = reqwest::get(&url)
-        .await
-        .map_err(|e| UiError::from(format!("Request error: {e}")))?
-        .text()
-        .await
-        .map_err(|e| UiError::from(format!("Read error: {e}")))?;
+    let res = http::cached_text(&app, &api, api.client().get(url), MOD_INFO_CACHE).await?;
 
     let json: serde_json::Value =
-        serde_json::from_str(&res).map_err(|e| UiError::from(format!("JSON parse error: {e}")))?;
+        serde_json::from_str(&res).map_err(|e| UiError::parse("JSON parse error", e))?;
     Ok(json)
 }
 
+// Installs the newest release of a mod that supports the installation's game version
 #[command]
-pub async fn add_mod_to_installation(path: String, url: String) -> Result<String, UiError> {
-    // Download the mod from the url and save it to the Mods directory inside the path
-    let pb = PathBuf::from(path).join("Mods");
-    if !pb.exists() {
-        std::fs::create_dir_all(&pb).map_err(|e| UiError {
-            name: "create_dir_failed".into(),
-            message: format!("Failed to create directory: {e}"),
+pub async fn install_mod(
+    app: AppHandle,
+    options: InstallModParams,
+) -> Result<InstalledRelease, UiError> {
+    let installation = store::find(&app, options.installation_id)?;
+    let info = fetch_mod_info(app.clone(), options.modid.clone()).await?;
+    let release = best_release(&info.details.releases, &installation.version)
+        .cloned()
+        .ok_or_else(|| {
+            UiError::not_found(format!(
+                "{} has no release for game version {}",
+                info.details.name, installation.version
+            ))
+            .with_details(json!({ "modid": options.modid, "game_version": installation.version }))
         })?;
+
+    let mods_dir = PathBuf::from(&installation.path).join("Mods");
+    if mods_dir.is_dir() {
+        let installed = get_mods(installation.path.clone())?.mods;
+        if let Some(existing) = installed
+            .iter()
+            .find(|m| m.modid.eq_ignore_ascii_case(&release.modidstr))
+        {
+            return Err(UiError::new(
+                ErrorCode::InvalidState,
+                format!("{} is already installed", existing.name),
+            )
+            .with_details(json!({
+                "modid": existing.modid,
+                "version": existing.version,
+                "path": existing.path,
+                "enabled": existing.enabled,
+            })));
+        }
     }
-    let response = reqwest::get(&url)
-        .await
-        .map_err(|e| UiError::from(format!("Request error: {e}")))?;
-    if !response.status().is_success() {
-        return Err(UiError {
-            name: "http_error".into(),
-            message: format!("HTTP error: {}", response.status()),
-        });
-    }
-    let filename = url
-        .split('=')
-        .last()
-        .ok_or_else(|| UiError::from("Invalid URL"))?;
-    let filepath = pb.join(filename);
-    let mut file = File::create(&filepath).map_err(|e| UiError {
-        name: "create_file_failed".into(),
-        message: format!("Failed to create file: {e}"),
-    })?;
-    let content = response.bytes().await.map_err(|e| UiError {
-        name: "read_response_failed".into(),
-        message: format!("Failed to read response: {e}"),
-    })?;
-    file.write_all(&content).map_err(|e| UiError {
-        name: "write_file_failed".into(),
-        message: format!("Failed to write file: {e}"),
-    })?;
+
+    let path = download::download(
+        &app,
+        DownloadRequest {
+            url: release.mainfile.clone(),
+            destpath: mods_dir.to_string_lossy().into_owned(),
+            emitevent: options.emitevent,
+            extract: false,
+            extractdir: None,
+            zipsubfolderprefix: None,
+            expectedsize: None,
+            expectedhash: None,
+            stripcomponents: None,
+            filename: Some(release.filename.clone()),
+        },
+        None,
+    )
+    .await?;
+
+    // The file has to be a mod zip, and the mod that was asked for
+    let modid = match read_modinfo(&path) {
+        Ok(modinfo) => modinfo
+            .get("modid")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string(),
+        Err(e) => {
+            std::fs::remove_file(&path).ok();
+            return Err(e);
+        }
+    };
+    if !modid.is_empty() && !modid.eq_ignore_ascii_case(&release.modidstr) {
+        std::fs::remove_file(&path).ok();
+        return Err(UiError::new(
+            ErrorCode::Verification,
+            format!(
+                "Downloaded release is mod {modid}, expected {}",
+                release.modidstr
+            ),
+        )
+        .with_details(json!({ "expected": release.modidstr, "actual": modid })));
+    }
+
+    Ok(InstalledRelease {
+        modid: release.modidstr.clone(),
+        name: info.details.name,
+        path: path.to_string_lossy().into_owned(),
+        release,
+    })
+}
+
+#[command]
+pub async fn add_mod_to_installation(
+    app: AppHandle,
+    path: String,
+    url: String,
+    emitevent: Option<String>,
+) -> Result<String, UiError> {
+    // Stream the mod into the Mods directory inside the path. It only shows up under
+    // its real name once complete, so get_mods never sees a half written zip.
+    let pb = PathBuf::from(path).join("Mods");
+    // Links like ".../download?dl=name.zip" carry the name in the query
+    let filename = reqwest::Url::parse(&url).ok().and_then(|u| {
+        u.query_pairs()
+            .find(|(key, _)| key == "dl")
+            .map(|(_, name)| name.into_owned())
+    });
+    download::download(
+        &app,
+        DownloadRequest {
+            url,
+            destpath: pb.to_string_lossy().into_owned(),
+            emitevent: emitevent.unwrap_or_else(|| "download://mod".into()),
+            extract: false,
+            extractdir: None,
+            zipsubfolderprefix: None,
+            expectedsize: None,
+            expectedhash: None,
+            stripcomponents: None,
+            filename,
+        },
+        None,
+    )
+    .await?;
     Ok("added".into())
 }
 
 #[command]
 pub fn get_mods(path: String) -> Result<ModsResult, UiError> {
-    let mods_path = PathBuf::from(path).join("Mods");
+    let installation_path = PathBuf::from(path);
+    let mods_path = installation_path.join("Mods");
     if !mods_path.exists() || !mods_path.is_dir() {
-        return Err(UiError {
-            name: "not_found".into(),
-            message: mods_path.to_string_lossy().into_owned(),
-        });
+        return Err(UiError::not_found(mods_path.to_string_lossy().into_owned()));
     }
 
     let mut mods: Vec<OutputMod> = Vec::new();
@@ -280,17 +645,41 @@ pub fn get_mods(path: String) -> Result<ModsResult, UiError> {
     let read_dir = match std::fs::read_dir(&mods_path) {
         Ok(rd) => rd,
         Err(e) => {
-            return Err(UiError {
-                name: "io_error".into(),
-                message: format!(
+            return Err(UiError::new(
+                ErrorCode::Io,
+                format!(
                     "Failed to read directory {}: {}",
                     mods_path.to_string_lossy(),
                     e
                 ),
-            });
+            ));
         }
     };
+    scan_mods(read_dir, &mods_path, true, &mut mods, &mut errors);
 
+    let disabled_path = installation_path.join(DISABLED_DIR);
+    if disabled_path.is_dir() {
+        match std::fs::read_dir(&disabled_path) {
+            Ok(read_dir) => scan_mods(read_dir, &disabled_path, false, &mut mods, &mut errors),
+            Err(e) => errors.push(ModError {
+                file: disabled_path.to_string_lossy().into_owned(),
+                stage: "read_dir".into(),
+                message: e.to_string(),
+            }),
+        }
+    }
+
+    Ok(ModsResult { mods, errors })
+}
+
+// Reads the modinfo.json of every zip in one mods directory
+fn scan_mods(
+    read_dir: std::fs::ReadDir,
+    mods_path: &Path,
+    enabled: bool,
+    mods: &mut Vec<OutputMod>,
+    errors: &mut Vec<ModError>,
+) {
     for entry_res in read_dir {
         let entry = match entry_res {
             Ok(e) => e,
@@ -415,12 +804,25 @@ pub fn get_mods(path: String) -> Result<ModsResult, UiError> {
                         .and_then(|v| v.as_str())
                         .unwrap_or("0.0.0")
                         .to_string();
+                    let dependencies = json
+                        .get("dependencies")
+                        .and_then(|v| v.as_object())
+                        .map(|obj| {
+                            obj.iter()
+                                .map(|(k, v)| {
+                                    (k.to_lowercase(), v.as_str().unwrap_or("").to_string())
+                                })
+                                .collect()
+                        })
+                        .unwrap_or_default();
                     mods.push(OutputMod {
                         modid,
                         name,
                         authors,
                         version,
                         path,
+                        dependencies,
+                        enabled,
                     });
                     found_valid = true;
                     // If you only want the first valid modinfo.json per zip, break here:
@@ -456,54 +858,39 @@ pub fn get_mods(path: String) -> Result<ModsResult, UiError> {
             });
         }
     }
-
-    Ok(ModsResult { mods, errors })
 }
 
 #[command]
 pub fn get_mod_configs(app: AppHandle, installation_id: u64) -> Result<Vec<Value>, UiError> {
-    let installation_zustand = app.zustand().get("installations", "installations").unwrap();
-    let installation_json: Value = serde_json::from_value(installation_zustand).unwrap();
-    // Find installation with matching id
-    let installation = installation_json
-        .as_array()
-        .and_then(|arr| {
-            arr.iter()
-                .find(|inst| inst["id"].as_u64() == Some(installation_id))
-        });
-
-    let installation = match installation {
-        Some(inst) => inst,
-        None => {
-            // Optionally, log the error or handle it as needed
-            return Err(UiError {
-                name: "installation_not_found".into(),
-                message: format!("Installation with id {} not found", installation_id),
-            });
-        }
-    };
+    let installation = store::find(&app, installation_id)?;
 
-    let mod_config_path = Path::new(installation["path"].as_str().unwrap()).join("ModConfig");
+    let mod_config_path = Path::new(&installation.path).join("ModConfig");
     if !mod_config_path.exists() || !mod_config_path.is_dir() {
-        return Err(UiError {
-            name: "not_found".into(),
-            message: mod_config_path.to_string_lossy().into_owned(),
-        });
+        return Err(UiError::not_found(
+            mod_config_path.to_string_lossy().into_owned(),
+        ));
     }
     // Traverse the ModConfig directory and read all .json files
     let mut configs = Vec::new();
-    for entry in std::fs::read_dir(mod_config_path).map_err(|e| UiError::from(format!("Read dir error: {e}")))? {
-        let entry = entry.map_err(|e| UiError::from(format!("Dir entry error: {e}")))?;
+    for entry in std::fs::read_dir(mod_config_path).map_err(|e| UiError::io("Read dir error", e))? {
+        let entry = entry.map_err(|e| UiError::io("Dir entry error", e))?;
         let path = entry.path();
         if path.is_file() {
             if let Some(ext) = path.extension() {
                 if ext == "json" {
                     // Output should be an array of objects with "filename" and "content"
-                    let filename = path.file_name().and_then(|s| s.to_str()).unwrap_or("").to_string();
-                    let mut file = File::open(&path).map_err(|e| UiError::from(format!("Open file error: {e}")))?;
+                    let filename = path
+                        .file_name()
+                        .and_then(|s| s.to_str())
+                        .unwrap_or("")
+                        .to_string();
+                    let mut file =
+                        File::open(&path).map_err(|e| UiError::io("Open file error", e))?;
                     let mut content = String::new();
-                    file.read_to_string(&mut content).map_err(|e| UiError::from(format!("Read file error: {e}")))?;
-                    let json_content: Value = serde_json::from_str(&content).map_err(|e| UiError::from(format!("Parse JSON error: {e}")))?;
+                    file.read_to_string(&mut content)
+                        .map_err(|e| UiError::io("Read file error", e))?;
+                    let json_content: Value = serde_json::from_str(&content)
+                        .map_err(|e| UiError::parse("Parse JSON error", e))?;
                     configs.push(serde_json::json!({
                         "filename": filename,
                         "content": json_content
@@ -517,129 +904,159 @@ pub fn get_mod_configs(app: AppHandle, installation_id: u64) -> Result<Vec<Value
 }
 
 #[command]
-pub fn save_mod_config(app: AppHandle, installation_id: u64, file: String, new_code: String) -> Result<(), UiError> {
-    // Find installation path from zustand
-    let installation_zustand = app.zustand().get("installations", "installations").unwrap();
-    let installation_json: Value = serde_json::from_value(installation_zustand).unwrap();
-    let installation = installation_json
-        .as_array()
-        .and_then(|arr| {
-            arr.iter()
-                .find(|inst| inst["id"].as_u64() == Some(installation_id))
-        });
-
-    let installation = match installation {
-        Some(inst) => inst,
-        None => {
-            return Err(UiError {
-                name: "installation_not_found".into(),
-                message: format!("Installation with id {} not found", installation_id),
-            });
-        }
-    };
+pub fn save_mod_config(
+    app: AppHandle,
+    installation_id: u64,
+    file: String,
+    new_code: String,
+) -> Result<(), UiError> {
+    let installation = store::find(&app, installation_id)?;
 
-    let mod_config_path = Path::new(installation["path"].as_str().unwrap()).join("ModConfig");
+    let mod_config_path = Path::new(&installation.path).join("ModConfig");
     if !mod_config_path.exists() || !mod_config_path.is_dir() {
-        return Err(UiError {
-            name: "not_found".into(),
-            message: mod_config_path.to_string_lossy().into_owned(),
-        });
+        return Err(UiError::not_found(
+            mod_config_path.to_string_lossy().into_owned(),
+        ));
     }
 
     let file_path = mod_config_path.join(&file);
     if !file_path.exists() || !file_path.is_file() {
-        return Err(UiError {
-            name: "file_not_found".into(),
-            message: file_path.to_string_lossy().into_owned(),
-        });
+        return Err(UiError::not_found(file_path.to_string_lossy().into_owned()));
     }
 
     // Write new code to the file
-    let mut f = File::create(&file_path).map_err(|e| UiError {
-        name: "create_file_failed".into(),
-        message: format!("Failed to create file: {e}"),
-    })?;
-    f.write_all(new_code.as_bytes()).map_err(|e| UiError {
-        name: "write_file_failed".into(),
-        message: format!("Failed to write file: {e}"),
-    })?;
+    let mut f = File::create(&file_path).map_err(|e| UiError::io("Failed to create file", e))?;
+    f.write_all(new_code.as_bytes())
+        .map_err(|e| UiError::io("Failed to write file", e))?;
 
     Ok(())
 }
 
 #[command]
-pub async fn get_mod_updates(params: String) -> Result<Value, UiError> {
-    let client = reqwest::Client::new();
-    let url = format!("https://mods.vintagestory.at/api/updates?mods={}", params);
-    let res = client
-        .get(&url)
-        .send()
-        .await
-        .map_err(|e| UiError::from(format!("Request error: {e}")))?;
+pub async fn get_mod_updates(app: AppHandle, params: String) -> Result<Value, UiError> {
+    let api = http::api(&app);
+    let url = api.url(Service::ModDb, &format!("/api/updates?mods={}", params));
+    let res = api.client().get(&url).send().await?;
     if !res.status().is_success() {
-        return Err(UiError {
-            name: "http_error".into(),
-            message: format!("HTTP error: {}", res.status()),
-        });
-    }
-    let res_text = res
-        .text()
-        .await
-        .map_err(|e| UiError::from(format!("Read error: {e}")))?;
+        return Err(UiError::http_status(res.status()));
+    }
+    let res_text = res.text().await?;
     let json: Value =
-        serde_json::from_str(&res_text).map_err(|e| UiError::from(format!("Parse error: {e}")))?;
+        serde_json::from_str(&res_text).map_err(|e| UiError::parse("Parse error", e))?;
     Ok(json)
 }
 
+// Reads the first modinfo.json found anywhere in a mod zip
+pub fn read_modinfo(path: &Path) -> Result<Value, UiError> {
+    let file = File::open(path).map_err(|e| UiError::io("Failed to open mod file", e))?;
+    let mut archive = ZipArchive::new(file)
+        .map_err(|e| UiError::wrap(ErrorCode::InvalidData, "Failed to read mod archive", e))?;
+    for i in 0..archive.len() {
+        let mut entry = archive
+            .by_index(i)
+            .map_err(|e| UiError::wrap(ErrorCode::InvalidData, "Failed to read mod archive", e))?;
+        let is_modinfo = Path::new(entry.name())
+            .file_name()
+            .and_then(|s| s.to_str())
+            .map(|s| s.eq_ignore_ascii_case("modinfo.json"))
+            .unwrap_or(false);
+        if entry.is_dir() || !is_modinfo {
+            continue;
+        }
+        let mut contents = String::new();
+        entry
+            .read_to_string(&mut contents)
+            .map_err(|e| UiError::io("Failed to read modinfo.json", e))?;
+        return serde_json::from_str(&contents)
+            .map_err(|e| UiError::parse("Failed to parse modinfo.json", e));
+    }
+    Err(UiError::new(
+        ErrorCode::InvalidData,
+        format!("No modinfo.json found in {}", path.to_string_lossy()),
+    ))
+}
+
 #[command]
-pub fn get_installation_mods(app: AppHandle, id: i64) -> Result<Vec<OutputMod>, UiError> {
-    let installations_zustand = app.zustand().get("installations", "installations").unwrap();
-    let installations_json: Value = serde_json::from_value(installations_zustand).unwrap();
-    let installation = installations_json
-        .as_array()
-        .and_then(|arr| arr.iter().find(|inst| inst["id"].as_i64() == Some(id)))
-        .ok_or_else(|| UiError {
-            name: "not_found".into(),
-            message: format!("Installation with id {} not found", id),
-        })?;
-    let path = installation["path"].as_str().unwrap_or("");
-    get_mods(path.to_string())
-        .map(|res| res.mods)
-        .map_err(|e| UiError {
-            name: e.name,
-            message: e.message,
-        })
+pub fn get_installation_mods(app: AppHandle, id: u64) -> Result<Vec<OutputMod>, UiError> {
+    let installation = store::find(&app, id)?;
+    get_mods(installation.path).map(|res| res.mods)
+}
+
+// The mods the game will actually load
+pub fn enabled_mods(path: String) -> Result<Vec<OutputMod>, UiError> {
+    Ok(get_mods(path)?
+        .mods
+        .into_iter()
+        .filter(|m| m.enabled)
+        .collect())
+}
+
+// Checks that `modpath` is a mod file directly inside Mods/ or Mods.disabled/
+// and returns whether it is enabled
+fn locate_mod(installation_path: &Path, mod_file: &Path) -> Result<bool, UiError> {
+    if !mod_file.exists() || !mod_file.is_file() {
+        return Err(UiError::not_found(mod_file.to_string_lossy().into_owned()));
+    }
+    let parent = mod_file.parent();
+    if parent == Some(installation_path.join("Mods").as_path()) {
+        return Ok(true);
+    }
+    if parent == Some(installation_path.join(DISABLED_DIR).as_path()) {
+        return Ok(false);
+    }
+    Err(UiError::new(
+        ErrorCode::InvalidParams,
+        format!(
+            "Mod path {} is not inside the Mods directory of {}",
+            mod_file.to_string_lossy(),
+            installation_path.to_string_lossy()
+        ),
+    ))
+}
+
+// Moves a mod between Mods/ and Mods.disabled/ and returns its new path
+pub fn set_mod_enabled(
+    installation_path: &Path,
+    mod_file: &Path,
+    enabled: bool,
+) -> Result<PathBuf, UiError> {
+    if locate_mod(installation_path, mod_file)? == enabled {
+        return Ok(mod_file.to_path_buf());
+    }
+    let target_dir = installation_path.join(if enabled { "Mods" } else { DISABLED_DIR });
+    let target = target_dir.join(mod_file.file_name().unwrap_or_default());
+    if target.exists() {
+        return Err(UiError::new(
+            ErrorCode::InvalidState,
+            format!("{} already exists", target.to_string_lossy()),
+        ));
+    }
+    std::fs::create_dir_all(&target_dir)
+        .map_err(|e| UiError::io("Failed to create directory", e))?;
+    std::fs::rename(mod_file, &target).map_err(|e| UiError::io("Failed to move mod file", e))?;
+    Ok(target)
+}
+
+#[command]
+pub fn enable_mod(params: ModRemoveParams) -> Result<String, UiError> {
+    set_mod_enabled(Path::new(&params.path), Path::new(&params.modpath), true)
+        .map(|path| path.to_string_lossy().into_owned())
+}
+
+#[command]
+pub fn disable_mod(params: ModRemoveParams) -> Result<String, UiError> {
+    set_mod_enabled(Path::new(&params.path), Path::new(&params.modpath), false)
+        .map(|path| path.to_string_lossy().into_owned())
 }
 
 #[command]
 pub async fn remove_mod_from_installation(params: ModRemoveParams) -> Result<String, UiError> {
     let mods_path = PathBuf::from(&params.path).join("Mods");
     if !mods_path.exists() || !mods_path.is_dir() {
-        return Err(UiError {
-            name: "not_found".into(),
-            message: mods_path.to_string_lossy().into_owned(),
-        });
+        return Err(UiError::not_found(mods_path.to_string_lossy().into_owned()));
     }
     let mod_file = PathBuf::from(&params.modpath);
-    if !mod_file.exists() || !mod_file.is_file() {
-        return Err(UiError {
-            name: "not_found".into(),
-            message: mod_file.to_string_lossy().into_owned(),
-        });
-    }
-    if mod_file.parent().map(|p| p != mods_path).unwrap_or(true) {
-        return Err(UiError {
-            name: "invalid_path".into(),
-            message: format!(
-                "Mod path {} is not inside Mods directory {}",
-                mod_file.to_string_lossy(),
-                mods_path.to_string_lossy()
-            ),
-        });
-    }
-    std::fs::remove_file(&mod_file).map_err(|e| UiError {
-        name: "remove_failed".into(),
-        message: format!("Failed to remove mod file: {e}"),
-    })?;
+    locate_mod(Path::new(&params.path), &mod_file)?;
+    std::fs::remove_file(&mod_file).map_err(|e| UiError::io("Failed to remove mod file", e))?;
     Ok("removed".into())
 }
@@ -1,9 +1,13 @@
 use quick_xml::de::from_str;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use tauri::command;
+use tauri::{command, AppHandle};
 
+use super::cache::CachePolicy;
 use super::errors::UiError;
+use super::http::{self, Service};
+
+const NEWS_CACHE: CachePolicy = CachePolicy::minutes(30, 24 * 60);
 
 #[allow(non_snake_case)]
 #[derive(Debug, Serialize, Deserialize)]
@@ -26,15 +30,11 @@ pub struct Rss {
 }
 
 #[command]
-pub async fn fetch_news() -> Result<serde_json::Value, UiError> {
-    let url = "https://www.vintagestory.at/forums/forum/7-news.xml/";
-    let xml = reqwest::get(url)
-        .await
-        .map_err(|e| UiError::from(format!("Request error: {e}")))?
-        .text()
-        .await
-        .map_err(|e| UiError::from(format!("Read error: {e}")))?;
+pub async fn fetch_news(app: AppHandle) -> Result<serde_json::Value, UiError> {
+    let api = http::api(&app);
+    let url = api.url(Service::News, "/forums/forum/7-news.xml/");
+    let xml = http::cached_text(&app, &api, api.client().get(url), NEWS_CACHE).await?;
 
-    let rss: Rss = from_str(&xml).map_err(|e| UiError::from(format!("XML parse error: {e}")))?;
+    let rss: Rss = from_str(&xml).map_err(|e| UiError::parse("XML parse error", e))?;
     Ok(json!(rss.channel.item))
 }
@@ -0,0 +1,181 @@
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    process::Child,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use tauri::{command, AppHandle, Emitter, Manager, Runtime, State};
+
+use super::bisect;
+use super::crashes::{self, CrashReport};
+use super::errors::{ErrorCode, UiError};
+use super::store;
+use super::util::now_millis;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RunningGame {
+    pub pid: u32,
+    pub installation_id: u64,
+    // Milliseconds since the epoch
+    pub started_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GameExit {
+    pub pid: u32,
+    pub installation_id: u64,
+    pub code: Option<i32>,
+    pub success: bool,
+    // Milliseconds
+    pub duration: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CrashPayload {
+    pub pid: u32,
+    pub installation_id: u64,
+    pub report: CrashReport,
+}
+
+struct TrackedGame {
+    info: RunningGame,
+    child: Child,
+    started: Instant,
+}
+
+#[derive(Default)]
+pub struct GameProcesses {
+    games: Mutex<HashMap<u32, TrackedGame>>,
+}
+
+// Takes ownership of a freshly launched game and watches it until it exits
+pub fn track<R: Runtime>(app: &AppHandle<R>, installation_id: u64, child: Child) {
+    let info = RunningGame {
+        pid: child.id(),
+        installation_id,
+        started_at: now_millis(),
+    };
+    app.state::<GameProcesses>().games.lock().unwrap().insert(
+        info.pid,
+        TrackedGame {
+            info: info.clone(),
+            child,
+            started: Instant::now(),
+        },
+    );
+    app.emit("game://started", &info).ok();
+    tauri::async_runtime::spawn(watch(app.clone(), info.pid, info.started_at));
+}
+
+async fn watch<R: Runtime>(app: AppHandle<R>, pid: u32, started_at: u64) {
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        let exited = {
+            let processes = app.state::<GameProcesses>();
+            let mut games = processes.games.lock().unwrap();
+            let Some(game) = games.get_mut(&pid) else {
+                return;
+            };
+            match game.child.try_wait() {
+                Ok(Some(status)) => Some((status.code(), status.success())),
+                Ok(None) => None,
+                // The process can no longer be queried, treat it as gone
+                Err(_) => Some((None, false)),
+            }
+            .map(|(code, success)| {
+                let game = games.remove(&pid).unwrap();
+                GameExit {
+                    pid,
+                    installation_id: game.info.installation_id,
+                    code,
+                    success,
+                    duration: game.started.elapsed().as_millis() as u64,
+                }
+            })
+        };
+        if let Some(exit) = exited {
+            record_playtime(&app, &exit);
+            app.emit("game://exited", &exit).ok();
+            let crashed = !exit.success && report_crash(&app, &exit, started_at).await;
+            bisect::observe_exit(&app, &exit, crashed);
+            return;
+        }
+    }
+}
+
+// Emits "game://crashed" when the game left a crash log during this session
+async fn report_crash<R: Runtime>(app: &AppHandle<R>, exit: &GameExit, started_at: u64) -> bool {
+    let Ok(installation) = store::find(app, exit.installation_id) else {
+        return false;
+    };
+    let data_path = PathBuf::from(installation.path);
+    let report = tauri::async_runtime::spawn_blocking(move || crashes::analyze(&data_path)).await;
+    match report {
+        Ok(Ok(Some(report))) if report.crashed_at.unwrap_or(0) >= started_at => {
+            app.emit(
+                "game://crashed",
+                CrashPayload {
+                    pid: exit.pid,
+                    installation_id: exit.installation_id,
+                    report,
+                },
+            )
+            .ok();
+            true
+        }
+        Ok(Err(e)) => {
+            log::warn!("Failed to analyze crash: {e}");
+            false
+        }
+        _ => false,
+    }
+}
+
+fn record_playtime<R: Runtime>(app: &AppHandle<R>, exit: &GameExit) {
+    let Ok(mut installation) = store::find(app, exit.installation_id) else {
+        // Removed while the game was running
+        return;
+    };
+    installation.total_time_played += exit.duration;
+    if let Err(e) = store::update(app, &installation) {
+        log::warn!("Failed to record play time: {e}");
+    }
+}
+
+pub fn is_running<R: Runtime>(app: &AppHandle<R>, installation_id: u64) -> bool {
+    app.state::<GameProcesses>()
+        .games
+        .lock()
+        .unwrap()
+        .values()
+        .any(|game| game.info.installation_id == installation_id)
+}
+
+#[command]
+pub fn list_running_games(processes: State<'_, GameProcesses>) -> Vec<RunningGame> {
+    let mut games: Vec<RunningGame> = processes
+        .games
+        .lock()
+        .unwrap()
+        .values()
+        .map(|game| game.info.clone())
+        .collect();
+    games.sort_by_key(|game| game.started_at);
+    games
+}
+
+#[command]
+pub fn kill_game(processes: State<'_, GameProcesses>, pid: u32) -> Result<(), UiError> {
+    let mut games = processes.games.lock().unwrap();
+    let game = games
+        .get_mut(&pid)
+        .ok_or_else(|| UiError::not_found(format!("No running game with pid {pid}")))?;
+    // The watcher notices the exit and emits "game://exited"
+    game.child
+        .kill()
+        .map_err(|e| UiError::wrap(ErrorCode::Unknown, "Failed to kill game", e))
+}
@@ -1,28 +1,19 @@
 use serde_json::Value;
-use tauri::command;
+use tauri::{command, AppHandle};
 
+use super::cache::CachePolicy;
 use super::errors::UiError;
+use super::http::{self, Service};
+
+// Servers come and go, keep this short
+const SERVERS_CACHE: CachePolicy = CachePolicy::minutes(1, 10);
 
 #[command]
-pub async fn fetch_public_servers() -> Result<Value, UiError> {
-    let client = reqwest::Client::new();
-    let url = "https://masterserver.vintagestory.at/api/v1/servers/list";
-    let res = client
-        .get(url)
-        .send()
-        .await
-        .map_err(|e| UiError::from(format!("Request error: {e}")))?;
-    if !res.status().is_success() {
-        return Err(UiError {
-            name: "http_error".into(),
-            message: format!("HTTP error: {}", res.status()),
-        });
-    }
-    let res_text = res
-        .text()
-        .await
-        .map_err(|e| UiError::from(format!("Read error: {e}")))?;
+pub async fn fetch_public_servers(app: AppHandle) -> Result<Value, UiError> {
+    let api = http::api(&app);
+    let url = api.url(Service::MasterServer, "/api/v1/servers/list");
+    let res_text = http::cached_text(&app, &api, api.client().get(url), SERVERS_CACHE).await?;
     let json: Value =
-        serde_json::from_str(&res_text).map_err(|e| UiError::from(format!("Parse error: {e}")))?;
+        serde_json::from_str(&res_text).map_err(|e| UiError::parse("Parse error", e))?;
     Ok(json)
 }
@@ -0,0 +1,126 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tauri::{AppHandle, Runtime};
+use tauri_plugin_zustand::ManagerExt;
+
+use super::errors::{ErrorCode, UiError};
+
+const STORE_ID: &str = "installations";
+const STORE_KEY: &str = "installations";
+
+// Mirrors the `Installation` type in src/stores/installations.ts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Installation {
+    pub id: u64,
+    pub name: String,
+    pub path: String,
+    pub version: String,
+    #[serde(default)]
+    pub index: u64,
+    #[serde(default)]
+    pub start_params: String,
+    #[serde(default)]
+    pub icon: Option<String>,
+    #[serde(default)]
+    pub favorite: bool,
+    // Milliseconds since the epoch, as produced by Date.now()
+    #[serde(default)]
+    pub last_time_played: u64,
+    // Milliseconds
+    #[serde(default)]
+    pub total_time_played: u64,
+    // Fields the frontend added that we don't know about yet, kept on write
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+fn load_raw<R: Runtime>(app: &AppHandle<R>) -> Result<Vec<Value>, UiError> {
+    let value = app
+        .zustand()
+        .get(STORE_ID, STORE_KEY)
+        .unwrap_or(Value::Array(vec![]));
+    match value {
+        Value::Array(arr) => Ok(arr),
+        Value::Null => Ok(vec![]),
+        _ => Err(UiError::new(
+            ErrorCode::InvalidData,
+            "Installations data is not an array",
+        )),
+    }
+}
+
+fn parse(value: Value) -> Result<Installation, UiError> {
+    serde_json::from_value(value)
+        .map_err(|e| UiError::wrap(ErrorCode::InvalidData, "Malformed installation entry", e))
+}
+
+fn entry_id(value: &Value) -> Option<u64> {
+    value.get("id").and_then(|v| v.as_u64())
+}
+
+pub fn find<R: Runtime>(app: &AppHandle<R>, id: u64) -> Result<Installation, UiError> {
+    let value = load_raw(app)?
+        .into_iter()
+        .find(|v| entry_id(v) == Some(id))
+        .ok_or_else(|| UiError::not_found(format!("Installation with id {} not found", id)))?;
+    parse(value)
+}
+
+// Replaces the stored entry with the same id; the frontend store picks up the change
+pub fn update<R: Runtime>(app: &AppHandle<R>, installation: &Installation) -> Result<(), UiError> {
+    let mut arr = load_raw(app)?;
+    let slot = arr
+        .iter_mut()
+        .find(|v| entry_id(v) == Some(installation.id))
+        .ok_or_else(|| {
+            UiError::not_found(format!(
+                "Installation with id {} not found",
+                installation.id
+            ))
+        })?;
+    *slot = serde_json::to_value(installation)?;
+    app.zustand()
+        .set(STORE_ID, STORE_KEY, Value::Array(arr))
+        .map_err(|e| UiError::io("Failed to save installations", e))
+}
+
+pub fn all<R: Runtime>(app: &AppHandle<R>) -> Result<Vec<Installation>, UiError> {
+    load_raw(app)?.into_iter().map(parse).collect()
+}
+
+// Same uniqueness rules the frontend store applies, checked before any files are
+// written for a new installation
+pub fn ensure_unique<R: Runtime>(
+    app: &AppHandle<R>,
+    installation: &Installation,
+) -> Result<(), UiError> {
+    let existing = all(app)?;
+    let clash = existing.iter().find_map(|i| {
+        if i.id == installation.id {
+            Some(format!("ID \"{}\"", i.id))
+        } else if i.path == installation.path {
+            Some(format!("path \"{}\"", i.path))
+        } else if i.name == installation.name {
+            Some(format!("name \"{}\"", i.name))
+        } else {
+            None
+        }
+    });
+    if let Some(clash) = clash {
+        return Err(UiError::new(
+            ErrorCode::InvalidState,
+            format!("Installation with {clash} already exists"),
+        ));
+    }
+    Ok(())
+}
+
+pub fn insert<R: Runtime>(app: &AppHandle<R>, installation: &Installation) -> Result<(), UiError> {
+    ensure_unique(app, installation)?;
+    let mut arr = load_raw(app)?;
+    arr.push(serde_json::to_value(installation)?);
+    app.zustand()
+        .set(STORE_ID, STORE_KEY, Value::Array(arr))
+        .map_err(|e| UiError::io("Failed to save installations", e))
+}
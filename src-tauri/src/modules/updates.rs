@@ -0,0 +1,304 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+use tauri::{command, AppHandle};
+
+use super::download::{self, DownloadRequest};
+use super::errors::{ErrorCode, UiError};
+use super::http::{self, Service};
+use super::mods::{self, ModRelease};
+use super::store;
+
+// Old zips are kept here, next to Mods/ so the game doesn't load them
+const ROLLBACK_DIR: &str = "Mods.rollback";
+// New releases are downloaded here first, on the same filesystem so moving them in is atomic
+const STAGING_DIR: &str = "Mods.staging";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModUpdate {
+    pub modid: String,
+    pub name: String,
+    pub current_file: String,
+    pub current_version: String,
+    pub target: ModRelease,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AppliedUpdate {
+    pub modid: String,
+    pub version: String,
+    pub path: String,
+    pub rollback_path: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct UpdatesResponse {
+    #[serde(default, deserialize_with = "lenient_updates")]
+    updates: HashMap<String, ModRelease>,
+}
+
+// The api sends an empty array instead of an empty object when nothing needs updating
+fn lenient_updates<'de, D>(deserializer: D) -> Result<HashMap<String, ModRelease>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Value::deserialize(deserializer)? {
+        Value::Object(map) => {
+            serde_json::from_value(Value::Object(map)).map_err(serde::de::Error::custom)
+        }
+        _ => Ok(HashMap::new()),
+    }
+}
+
+fn modid_of(path: &Path) -> Result<String, UiError> {
+    Ok(mods::read_modinfo(path)?
+        .get("modid")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string())
+}
+
+// Moves `source` into Mods/ as `filename`, replacing `current`. A copy of `current` is kept in
+// the rollback dir first, so Mods/ holds either the old or the new zip at every point.
+fn swap_in(
+    source: &Path,
+    filename: &str,
+    current: &Path,
+    rollback_dir: &Path,
+) -> Result<(PathBuf, PathBuf), UiError> {
+    let mods_dir = current
+        .parent()
+        .ok_or_else(|| UiError::new(ErrorCode::InvalidParams, "Mod file has no parent"))?;
+    let target = mods_dir.join(filename);
+    if target != current && target.exists() {
+        return Err(UiError::new(
+            ErrorCode::InvalidState,
+            format!("{} already exists", target.to_string_lossy()),
+        ));
+    }
+    fs::create_dir_all(rollback_dir)
+        .map_err(|e| UiError::io("Failed to create rollback directory", e))?;
+    let rollback_path = rollback_dir.join(current.file_name().unwrap_or_default());
+    fs::copy(current, &rollback_path)
+        .map_err(|e| UiError::io("Failed to keep a rollback copy", e))?;
+    // Same filesystem, so this either fully happens or not at all
+    fs::rename(source, &target).map_err(|e| UiError::io("Failed to move mod into place", e))?;
+    if target != current {
+        if let Err(e) = fs::remove_file(current) {
+            fs::remove_file(&target).ok();
+            return Err(UiError::io("Failed to remove the old mod file", e));
+        }
+    }
+    Ok((target, rollback_path))
+}
+
+fn mods_dir_of(installation_path: &str, file: &Path) -> Result<PathBuf, UiError> {
+    let mods_dir = PathBuf::from(installation_path).join("Mods");
+    if file.parent() != Some(mods_dir.as_path()) {
+        return Err(UiError::new(
+            ErrorCode::InvalidParams,
+            format!(
+                "Mod path {} is not inside Mods directory {}",
+                file.to_string_lossy(),
+                mods_dir.to_string_lossy()
+            ),
+        ));
+    }
+    if !file.is_file() {
+        return Err(UiError::not_found(file.to_string_lossy().into_owned()));
+    }
+    Ok(mods_dir)
+}
+
+#[command]
+pub async fn get_update_plan(
+    app: AppHandle,
+    installation_id: u64,
+) -> Result<Vec<ModUpdate>, UiError> {
+    let installation = store::find(&app, installation_id)?;
+    let installed = mods::enabled_mods(installation.path)?;
+    if installed.is_empty() {
+        return Ok(vec![]);
+    }
+    let params = installed
+        .iter()
+        .map(|m| format!("{}@{}", m.modid, m.version))
+        .collect::<Vec<_>>()
+        .join(",");
+    let api = http::api(&app);
+    let res = api
+        .client()
+        .get(api.url(Service::ModDb, "/api/updates"))
+        .query(&[("mods", params)])
+        .send()
+        .await?;
+    if !res.status().is_success() {
+        return Err(UiError::http_status(res.status()));
+    }
+    let json = res.json::<UpdatesResponse>().await?;
+
+    let mut plan: Vec<ModUpdate> = json
+        .updates
+        .into_iter()
+        .filter(|(_, release)| !release.mainfile.is_empty())
+        .filter_map(|(key, release)| {
+            let current = installed.iter().find(|m| {
+                m.modid.eq_ignore_ascii_case(&release.modidstr)
+                    || m.modid.eq_ignore_ascii_case(&key)
+            })?;
+            Some(ModUpdate {
+                modid: current.modid.clone(),
+                name: current.name.clone(),
+                current_file: current.path.clone(),
+                current_version: current.version.clone(),
+                url: release.mainfile.clone(),
+                target: release,
+            })
+        })
+        .collect();
+    plan.sort_by_key(|update| update.name.to_lowercase());
+    Ok(plan)
+}
+
+#[command]
+pub async fn apply_mod_update(
+    app: AppHandle,
+    installation_id: u64,
+    update: ModUpdate,
+    emitevent: Option<String>,
+) -> Result<AppliedUpdate, UiError> {
+    let installation = store::find(&app, installation_id)?;
+    let current = PathBuf::from(&update.current_file);
+    mods_dir_of(&installation.path, &current)?;
+    let filename = Path::new(&update.target.filename)
+        .file_name()
+        .and_then(|s| s.to_str())
+        .filter(|s| *s == update.target.filename)
+        .ok_or_else(|| {
+            UiError::new(
+                ErrorCode::InvalidData,
+                format!("Invalid release filename: {}", update.target.filename),
+            )
+        })?;
+
+    let staging_dir = PathBuf::from(&installation.path).join(STAGING_DIR);
+    let staged = download::download(
+        &app,
+        DownloadRequest {
+            url: update.url.clone(),
+            destpath: staging_dir.to_string_lossy().into_owned(),
+            emitevent: emitevent.unwrap_or_else(|| "download://mod".into()),
+            extract: false,
+            extractdir: None,
+            zipsubfolderprefix: None,
+            expectedsize: None,
+            expectedhash: None,
+            stripcomponents: None,
+            filename: Some(filename.to_string()),
+        },
+        None,
+    )
+    .await?;
+
+    let modid = match modid_of(&staged) {
+        Ok(modid) => modid,
+        Err(e) => {
+            fs::remove_file(&staged).ok();
+            return Err(e);
+        }
+    };
+    if !modid.eq_ignore_ascii_case(&update.modid) {
+        fs::remove_file(&staged).ok();
+        return Err(UiError::new(
+            ErrorCode::Verification,
+            format!(
+                "Downloaded release is mod {modid}, expected {}",
+                update.modid
+            ),
+        )
+        .with_details(json!({ "expected": update.modid, "actual": modid })));
+    }
+
+    let rollback_dir = PathBuf::from(&installation.path).join(ROLLBACK_DIR);
+    let (path, rollback_path) = match swap_in(&staged, filename, &current, &rollback_dir) {
+        Ok(paths) => paths,
+        Err(e) => {
+            fs::remove_file(&staged).ok();
+            return Err(e);
+        }
+    };
+    Ok(AppliedUpdate {
+        modid: update.modid,
+        version: update.target.modversion,
+        path: path.to_string_lossy().into_owned(),
+        rollback_path: rollback_path.to_string_lossy().into_owned(),
+    })
+}
+
+// Puts the most recent rollback copy of a mod back in place of the installed version
+#[command]
+pub fn rollback_mod_update(
+    app: AppHandle,
+    installation_id: u64,
+    modid: String,
+) -> Result<AppliedUpdate, UiError> {
+    let installation = store::find(&app, installation_id)?;
+    let current = mods::enabled_mods(installation.path.clone())?
+        .into_iter()
+        .find(|m| m.modid.eq_ignore_ascii_case(&modid))
+        .ok_or_else(|| UiError::not_found(format!("Mod {modid} is not installed")))?;
+    let current_path = PathBuf::from(&current.path);
+    let mods_dir = mods_dir_of(&installation.path, &current_path)?;
+
+    let rollback_dir = PathBuf::from(&installation.path).join(ROLLBACK_DIR);
+    let mut candidates = vec![];
+    if rollback_dir.is_dir() {
+        for entry in fs::read_dir(&rollback_dir)
+            .map_err(|e| UiError::io("Failed to read rollback directory", e))?
+            .flatten()
+        {
+            let path = entry.path();
+            if !path.is_file() || !modid_of(&path).is_ok_and(|id| id.eq_ignore_ascii_case(&modid)) {
+                continue;
+            }
+            let modified = entry.metadata().and_then(|m| m.modified()).ok();
+            candidates.push((modified, path));
+        }
+    }
+    candidates.sort_by_key(|(modified, _)| *modified);
+    let (_, source) = candidates
+        .pop()
+        .ok_or_else(|| UiError::not_found(format!("No rollback copy of {modid} found")))?;
+    let filename = source
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default()
+        .to_string();
+    let version = mods::read_modinfo(&source)?
+        .get("version")
+        .and_then(|v| v.as_str())
+        .unwrap_or("0.0.0")
+        .to_string();
+
+    // Move it out of the rollback dir first, the installed version is about to be copied there
+    let staged = mods_dir.join(format!("{filename}.part"));
+    fs::rename(&source, &staged).map_err(|e| UiError::io("Failed to stage rollback copy", e))?;
+    let (path, rollback_path) = match swap_in(&staged, &filename, &current_path, &rollback_dir) {
+        Ok(paths) => paths,
+        Err(e) => {
+            fs::rename(&staged, &source).ok();
+            return Err(e);
+        }
+    };
+    Ok(AppliedUpdate {
+        modid: current.modid,
+        version,
+        path: path.to_string_lossy().into_owned(),
+        rollback_path: rollback_path.to_string_lossy().into_owned(),
+    })
+}
@@ -0,0 +1,92 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use super::errors::{ErrorCode, UiError};
+
+// Milliseconds since the epoch, the unit every timestamp the launcher stores uses
+pub fn millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+pub fn now_millis() -> u64 {
+    millis(SystemTime::now())
+}
+
+// A broken archive is bad data, only failures of the file underneath are io errors
+pub fn zip_error(context: &str, e: zip::result::ZipError) -> UiError {
+    let code = match e {
+        zip::result::ZipError::Io(_) => ErrorCode::Io,
+        _ => ErrorCode::InvalidData,
+    };
+    UiError::wrap(code, context, e)
+}
+
+// Names of files we generate, e.g. "saves-<millis>.zip". Anything else coming from the
+// frontend could point outside the directory they live in
+pub fn valid_generated_name(name: &str, prefix: &str, suffix: &str) -> bool {
+    name.starts_with(prefix)
+        && name.ends_with(suffix)
+        && !name.contains(['/', '\\'])
+        && !name.contains("..")
+}
+
+// Cleans up a destination that a failed import or clone already wrote to, so it can be
+// retried. A directory that existed before is emptied rather than removed
+pub fn discard_dir(path: &Path, existed: bool) {
+    if !existed {
+        fs::remove_dir_all(path).ok();
+        return;
+    }
+    for entry in fs::read_dir(path).into_iter().flatten().flatten() {
+        let path = entry.path();
+        if entry.file_type().is_ok_and(|t| t.is_dir()) {
+            fs::remove_dir_all(&path).ok();
+        } else {
+            fs::remove_file(&path).ok();
+        }
+    }
+}
+
+fn part_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    path.with_file_name(name)
+}
+
+// Writes through "<path>.part" and renames it into place, so nobody ever sees half a
+// file. The part file is removed again when `write` fails
+pub fn write_atomic_with(
+    path: &Path,
+    write: impl FnOnce(&mut File) -> Result<(), UiError>,
+) -> Result<(), UiError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| UiError::io("Failed to create directory", e))?;
+    }
+    let partpath = part_path(path);
+    let result = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&partpath)
+        .map_err(|e| UiError::io(format!("Failed to create {}", partpath.display()), e))
+        .and_then(|mut file| write(&mut file));
+    if let Err(e) = result {
+        fs::remove_file(&partpath).ok();
+        return Err(e);
+    }
+    fs::rename(&partpath, path).map_err(|e| UiError::io("rename error", e))
+}
+
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> Result<(), UiError> {
+    write_atomic_with(path, |file| {
+        file.write_all(bytes)
+            .map_err(|e| UiError::io(format!("Failed to write {}", path.display()), e))
+    })
+}
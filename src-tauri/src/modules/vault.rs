@@ -0,0 +1,196 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    ChaCha20Poly1305, Nonce,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+use tauri::{command, AppHandle, Manager, Runtime, State};
+
+use super::errors::{ErrorCode, UiError};
+use super::util;
+
+// The vault key lives in the OS keyring under this name
+const KEYRING_SERVICE: &str = "StoryForge";
+const KEYRING_USER: &str = "vault-key";
+// Fallback for systems without a usable keyring, e.g. headless Linux without a secret service
+const KEY_FILE: &str = "vault.key";
+const VAULT_FILE: &str = "vault.bin";
+const NONCE_LEN: usize = 12;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Secrets {
+    pub sessionkey: String,
+    pub sessionsignature: String,
+}
+
+// Caches the key; the lock also serializes reads and writes of the vault file
+#[derive(Default)]
+pub struct Vault {
+    key: Mutex<Option<Vec<u8>>>,
+}
+
+fn vault_dir<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, UiError> {
+    app.path()
+        .app_data_dir()
+        .map_err(|e| UiError::io("Failed to resolve app data dir", e))
+}
+
+fn decode_key(encoded: &str) -> Result<Vec<u8>, UiError> {
+    STANDARD
+        .decode(encoded.trim())
+        .map_err(|e| UiError::wrap(ErrorCode::InvalidData, "Vault key is corrupt", e))
+}
+
+// Written to a temporary file that is private from the start and renamed into place,
+// so the key is never readable by others, not even briefly
+fn write_key_file(path: &Path, encoded: &str) -> Result<(), UiError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| UiError::io("Failed to create directory", e))?;
+    }
+    let partpath = path.with_file_name(format!("{KEY_FILE}.part"));
+    fs::remove_file(&partpath).ok();
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let written = options.open(&partpath).and_then(|mut file| {
+        file.write_all(encoded.as_bytes())
+            .and_then(|_| file.sync_all())
+    });
+    if let Err(e) = written {
+        fs::remove_file(&partpath).ok();
+        return Err(UiError::io("Failed to write vault key", e));
+    }
+    fs::rename(&partpath, path).map_err(|e| UiError::io("rename error", e))
+}
+
+// Once a key file exists it stays authoritative, so the vault does not get orphaned
+// when a keyring shows up later
+fn load_key(dir: &Path) -> Result<Vec<u8>, UiError> {
+    let key_file = dir.join(KEY_FILE);
+    if key_file.is_file() {
+        let encoded = fs::read_to_string(&key_file)
+            .map_err(|e| UiError::io("Failed to read vault key", e))?;
+        return decode_key(&encoded);
+    }
+
+    let vault = dir.join(VAULT_FILE);
+    let entry = keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER);
+    let keyring_error = match &entry {
+        Ok(entry) => match entry.get_password() {
+            Ok(encoded) => return decode_key(&encoded),
+            Err(keyring::Error::NoEntry) => None,
+            Err(e) => Some(e.to_string()),
+        },
+        Err(e) => Some(e.to_string()),
+    };
+    if let Some(e) = &keyring_error {
+        // The keyring may just be locked or not up yet, its key could still open the vault
+        if vault.exists() {
+            return Err(UiError::new(
+                ErrorCode::Unknown,
+                format!("Keyring unavailable, the vault can't be opened: {e}"),
+            ));
+        }
+        log::warn!("Keyring unavailable, using a key file: {e}");
+    } else if vault.exists() {
+        // The keyring answered and has no key, nothing can open this vault anymore
+        fs::rename(&vault, dir.join(format!("{VAULT_FILE}.orphaned")))
+            .map_err(|e| UiError::io("rename error", e))?;
+    }
+
+    let encoded = STANDARD.encode(ChaCha20Poly1305::generate_key(&mut OsRng).as_slice());
+    let stored =
+        keyring_error.is_none() && entry.is_ok_and(|entry| entry.set_password(&encoded).is_ok());
+    if !stored {
+        write_key_file(&key_file, &encoded)?;
+    }
+    decode_key(&encoded)
+}
+
+fn cipher(key: &[u8]) -> Result<ChaCha20Poly1305, UiError> {
+    ChaCha20Poly1305::new_from_slice(key)
+        .map_err(|e| UiError::wrap(ErrorCode::InvalidData, "Vault key is corrupt", e))
+}
+
+fn read_vault(dir: &Path, key: &[u8]) -> Result<HashMap<String, Secrets>, UiError> {
+    let path = dir.join(VAULT_FILE);
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+    let bytes = fs::read(&path).map_err(|e| UiError::io("Failed to read vault", e))?;
+    if bytes.len() < NONCE_LEN {
+        return Err(UiError::new(ErrorCode::InvalidData, "Vault is corrupt"));
+    }
+    let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+    let plaintext = cipher(key)?
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| UiError::new(ErrorCode::InvalidData, "Vault could not be decrypted"))?;
+    serde_json::from_slice(&plaintext).map_err(|e| UiError::parse("Vault is corrupt", e))
+}
+
+fn write_vault(dir: &Path, key: &[u8], secrets: &HashMap<String, Secrets>) -> Result<(), UiError> {
+    let plaintext = serde_json::to_vec(secrets)?;
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher(key)?
+        .encrypt(&nonce, plaintext.as_slice())
+        .map_err(|_| UiError::new(ErrorCode::Unknown, "Failed to encrypt vault"))?;
+    let mut bytes = nonce.as_slice().to_vec();
+    bytes.extend_from_slice(&ciphertext);
+
+    util::write_atomic(&dir.join(VAULT_FILE), &bytes)
+}
+
+impl Vault {
+    // Runs `f` on the decrypted vault and writes it back when `f` reports a change
+    fn with<R: Runtime, T>(
+        &self,
+        app: &AppHandle<R>,
+        f: impl FnOnce(&mut HashMap<String, Secrets>) -> (T, bool),
+    ) -> Result<T, UiError> {
+        let dir = vault_dir(app)?;
+        let mut key = self.key.lock().unwrap();
+        if key.is_none() {
+            *key = Some(load_key(&dir)?);
+        }
+        let key = key.as_deref().unwrap();
+        let mut secrets = read_vault(&dir, key)?;
+        let (result, changed) = f(&mut secrets);
+        if changed {
+            write_vault(&dir, key, &secrets)?;
+        }
+        Ok(result)
+    }
+}
+
+pub fn store_secrets<R: Runtime>(
+    app: &AppHandle<R>,
+    uid: &str,
+    secrets: Secrets,
+) -> Result<(), UiError> {
+    app.state::<Vault>().with(app, |vault| {
+        vault.insert(uid.to_string(), secrets);
+        ((), true)
+    })
+}
+
+pub fn get_secrets<R: Runtime>(app: &AppHandle<R>, uid: &str) -> Result<Option<Secrets>, UiError> {
+    app.state::<Vault>()
+        .with(app, |vault| (vault.get(uid).cloned(), false))
+}
+
+// Drops the secrets of an account that was removed in the UI
+#[command]
+pub fn forget_account(app: AppHandle, vault: State<'_, Vault>, uid: String) -> Result<(), UiError> {
+    vault.with(&app, |vault| ((), vault.remove(&uid).is_some()))
+}
@@ -1,6 +1,10 @@
 use tauri::{command, AppHandle, Manager};
 
-use super::errors::UiError;
+use super::cache::CachePolicy;
+use super::errors::{ErrorCode, UiError};
+use super::http::{self, Service};
+
+const VERSIONS_CACHE: CachePolicy = CachePolicy::minutes(60, 24 * 60);
 
 #[command]
 pub fn get_installed_versions(app: AppHandle) -> Result<Vec<String>, UiError> {
@@ -8,20 +12,16 @@ pub fn get_installed_versions(app: AppHandle) -> Result<Vec<String>, UiError> {
     let base_dir = app
         .path()
         .app_data_dir()
-        .expect("Failed to get app local data dir");
+        .map_err(|e| UiError::wrap(ErrorCode::Io, "Failed to resolve app data dir", e))?;
     let versions_dir = base_dir.join("versions");
     if !versions_dir.exists() || !versions_dir.is_dir() {
         return Ok(vec![]);
     }
     let mut versions = vec![];
-    for entry in std::fs::read_dir(versions_dir).map_err(|e| UiError {
-        name: "io_error".into(),
-        message: format!("Failed to read versions directory: {e}"),
-    })? {
-        let entry = entry.map_err(|e| UiError {
-            name: "io_error".into(),
-            message: format!("Failed to read directory entry: {e}"),
-        })?;
+    for entry in std::fs::read_dir(versions_dir)
+        .map_err(|e| UiError::io("Failed to read versions directory", e))?
+    {
+        let entry = entry.map_err(|e| UiError::io("Failed to read directory entry", e))?;
         if entry.path().is_dir() {
             if let Some(name) = entry.file_name().to_str() {
                 versions.push(name.to_string());
@@ -36,42 +36,28 @@ pub fn remove_installed_version(version: String, app: AppHandle) -> Result<Strin
     let versions_path = app
         .path()
         .app_data_dir()
-        .unwrap()
+        .map_err(|e| UiError::wrap(ErrorCode::Io, "Failed to resolve app data dir", e))?
         .join("versions")
         .join(&version);
     if !versions_path.exists() || !versions_path.is_dir() {
-        return Err(UiError {
-            name: "not_found".into(),
-            message: format!(
-                "Version directory not found: {}",
-                versions_path.to_string_lossy()
-            ),
-        });
+        return Err(UiError::not_found(format!(
+            "Version directory not found: {}",
+            versions_path.to_string_lossy()
+        )));
     }
-    std::fs::remove_dir_all(&versions_path).map_err(|e| UiError {
-        name: "remove_failed".into(),
-        message: format!("Failed to remove version directory: {e}"),
-    })?;
+    std::fs::remove_dir_all(&versions_path)
+        .map_err(|e| UiError::io("Failed to remove version directory", e))?;
     Ok("removed".into())
 }
 
 #[command]
-pub async fn fetch_versions() -> Result<Vec<String>, UiError> {
-    let res = reqwest::get("https://vsapi.betterjs.dev/versions")
-        .await
-        .map_err(|e| format!("Request error: {e}"))?;
-
-    if !res.status().is_success() {
-        return Err(UiError {
-            name: "http_error".into(),
-            message: format!("HTTP error: {}", res.status()),
-        });
-    }
+pub async fn fetch_versions(app: AppHandle) -> Result<Vec<String>, UiError> {
+    let api = http::api(&app);
+    let request = api.client().get(api.url(Service::VsApi, "/versions"));
+    let body = http::cached_text(&app, &api, request, VERSIONS_CACHE).await?;
 
-    let json = res
-        .json::<Vec<String>>()
-        .await
-        .map_err(|e| format!("JSON error: {e}"))?;
+    let json: Vec<String> =
+        serde_json::from_str(&body).map_err(|e| UiError::parse("JSON parse error", e))?;
 
     Ok(json)
 }
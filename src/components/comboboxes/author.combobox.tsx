@@ -30,7 +30,10 @@ const authorQuery = (search: string) => ({
 });
 
 export const AuthorCombobox = (
-	props: React.InputHTMLAttributes<HTMLInputElement>,
+	props: React.InputHTMLAttributes<HTMLInputElement> & {
+		// Called with the ModDB user id as well, null when cleared
+		onAuthorChange?: (name: string, id: number | null) => void;
+	},
 ) => {
 	const [open, setOpen] = useState(false);
 	const [internalValue, setInternalValue] = useState("");
@@ -38,11 +41,12 @@ export const AuthorCombobox = (
 
 	const actualValue = props.value || internalValue;
 
-	const handleValueChange = (value: string) => {
+	const handleValueChange = (value: string, id: number | null = null) => {
 		setInternalValue(value);
 		props.onChange?.({
 			target: { value },
 		} as React.ChangeEvent<HTMLInputElement>);
+		props.onAuthorChange?.(value, id);
 	};
 
 	const { data: authors, isLoading } = useQuery(
@@ -93,7 +97,10 @@ export const AuthorCombobox = (
 									onSelect={(v) =>
 										v === actualValue
 											? handleValueChange("")
-											: handleValueChange(value)
+											: handleValueChange(
+													value,
+													Number.isNaN(Number(key)) ? null : Number(key),
+												)
 									}
 									value={value}
 								>
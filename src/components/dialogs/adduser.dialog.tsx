@@ -21,6 +21,7 @@ import {
 	TooltipContent,
 	TooltipTrigger,
 } from "@/components/ui/tooltip";
+import type { UiError } from "@/lib/types";
 import { cn } from "@/lib/utils";
 import { useAccountStore } from "@/stores/accounts";
 import { useDialogStore } from "@/stores/dialogs";
@@ -28,8 +29,6 @@ import { useDialogStore } from "@/stores/dialogs";
 type SignInResponse = {
 	valid: number;
 	uid?: string;
-	sessionkey?: string;
-	sessionsignature?: string;
 	mptoken?: string;
 	entitlements?: unknown;
 	playername?: string;
@@ -100,7 +99,10 @@ export function AddUserDialog({ open }: { open: boolean }) {
 				form.getFieldMeta("email")?.errors.push("Invalid email or password");
 				form.getFieldMeta("password")?.errors.push("Invalid email or password");
 			} else if (error.message === "requiretotpcode") {
-				form.setFieldValue("prelogintoken", error.name);
+				form.setFieldValue(
+					"prelogintoken",
+					String((error as unknown as UiError).details?.prelogintoken ?? ""),
+				);
 				toast.info("Please enter your authenticator code to continue.");
 			} else if (error.message === "wrongtotpcode") {
 				form.setFieldValue("totpcode", "");
@@ -126,8 +128,6 @@ export function AddUserDialog({ open }: { open: boolean }) {
 			addUser({
 				email: variables.email,
 				playername: data.playername,
-				sessionkey: data.sessionkey,
-				sessionsignature: data.sessionsignature,
 				uid: data.uid,
 			});
 		},
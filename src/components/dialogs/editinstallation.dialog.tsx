@@ -29,7 +29,7 @@ import {
 import { useAppFolder } from "@/hooks/use-app-folder";
 import { useInstalledVersions } from "@/hooks/use-installed-versions";
 import { gameVersionsQuery } from "@/lib/queries";
-import type { ProgressPayload } from "@/lib/types";
+import type { DownloadInfo, ProgressPayload } from "@/lib/types";
 import {
 	compareSemverDesc,
 	makeStringFolderSafe,
@@ -61,13 +61,12 @@ export function EditInstallationDialog({
 	const { updateInstallation } = useInstallationsStore();
 	const { mutateAsync: downloadVersion } = useMutation({
 		mutationFn: async (version: string) => {
-			const url = (await invoke("get_download_link", {
+			const info = (await invoke("get_download_info", {
 				version,
-			})) as string;
-			if (!url) {
+			})) as DownloadInfo;
+			if (!info.url) {
 				throw new Error("Download URL not found in response");
 			}
-			const downloadUrl = url;
 			if (!appFolder) {
 				throw new Error("App folder not found");
 			}
@@ -76,7 +75,9 @@ export function EditInstallationDialog({
 				emitevent: `download://version:${version.replace(/\./g, "_")}`,
 				extract: true,
 				extractdir: `${appFolder}/versions/${version}`,
-				url: downloadUrl,
+				url: info.url,
+				expectedsize: info.filesize,
+				expectedhash: info.hash,
 				zipsubfolderprefix: zipfolderprefix(),
 			}) as Promise<string>;
 		},
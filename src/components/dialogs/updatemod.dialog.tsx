@@ -19,56 +19,11 @@ import {
 } from "@/components/ui/select";
 import { installedModsQueryKey } from "@/hooks/use-installed-mods";
 import { modUpdatesQueryKey } from "@/hooks/use-mod-updates";
-import type { ProgressPayload } from "@/lib/types";
+import type { ModInfo, ProgressPayload, Release } from "@/lib/types";
 import type { OutputMod } from "@/routes/install-mods/$id";
 import { useDialogStore } from "@/stores/dialogs";
 import type { Installation } from "@/stores/installations";
 
-type Release = {
-	releaseid: number;
-	mainfile: string;
-	filename: string;
-	fileid: number;
-	downloads: number;
-	tags: string[];
-	modidstr: string;
-	modversion: string;
-	created: string;
-	changelog: string | null;
-};
-
-type ModInfo = {
-	mod: {
-		modid: number;
-		assetid: number;
-		name: string;
-		text: string;
-		author: string;
-		urlalias: string;
-		logofilename: string | null;
-		logofile: string | null;
-		logofiledb: string | null;
-		homepageurl: string | null;
-		sourcecodeurl: string | null;
-		trailervideourl: string | null;
-		issuetrackerurl: string | null;
-		wikiurl: string | null;
-		downloads: number;
-		follows: number;
-		trendingpoints: number;
-		comments: number;
-		side: string;
-		type: string;
-		created: string;
-		lastreleased: string;
-		lastmodified: string;
-		tags: string[];
-		releases: Release[];
-		screenshots: string[];
-	};
-	statuscode: string;
-};
-
 export type UpdateModDialogProps = {
 	mod: OutputMod;
 	installation: Installation;
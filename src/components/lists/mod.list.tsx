@@ -18,12 +18,26 @@ import { useModUpdates } from "@/hooks/use-mod-updates";
 import { cn } from "@/lib/utils";
 import { useDialogStore } from "@/stores/dialogs";
 import type { Installation } from "@/stores/installations";
-import { useModsFilters } from "@/stores/modsFilters";
+import { type ModsFilters, useModsFilters } from "@/stores/modsFilters";
 import { Button } from "../ui/button";
 
 type ModsParams = {
 	versions: string[];
 	search: string;
+	tagids?: number[];
+	author?: number;
+	side?: "client" | "server" | "both";
+	modtype?: "mod" | "externaltool" | "other";
+	sortby?:
+		| "asset.created"
+		| "lastreleased"
+		| "downloads"
+		| "follows"
+		| "comments"
+		| "trendingpoints";
+	sortorder?: "asc" | "desc";
+	offset?: number;
+	limit?: number;
 };
 
 type Mod = {
@@ -45,9 +59,31 @@ type Mod = {
 	lastreleased: string;
 };
 
+type ModSearchResult = {
+	mods: Mod[];
+	total: number;
+	offset: number;
+	limit: number | null;
+};
+
+// The api sorts by everything but the name, which stays a local sort
+const apiSortBy: Partial<
+	Record<ModsFilters["sortBy"], NonNullable<ModsParams["sortby"]>>
+> = {
+	comments: "comments",
+	created: "asset.created",
+	downloads: "downloads",
+	follows: "follows",
+	trending: "trendingpoints",
+	updated: "lastreleased",
+};
+
 const modsQuery = (params: ModsParams) => ({
 	keepPreviousData: true,
-	queryFn: () => invoke("fetch_mods", { options: params }) as Promise<Mod[]>,
+	queryFn: () =>
+		(
+			invoke("fetch_mods", { options: params }) as Promise<ModSearchResult>
+		).then((result) => result.mods),
 	queryKey: ["mods", params],
 	refetchOnWindowFocus: false,
 });
@@ -66,13 +102,21 @@ export function ModList({
 		sortBy,
 		orderDirection,
 		author,
+		authorId,
 		setAuthor,
 		side,
 		category,
 	} = useModsFilters();
 	const { data: mods } = useQuery(
 		modsQuery({
+			author: authorId ?? undefined,
+			modtype: category,
 			search: searchText,
+			side: side !== "installed" ? side : undefined,
+			sortby: apiSortBy[sortBy],
+			// The toggle reads "Asc" while the store says descending
+			sortorder: orderDirection === "descending" ? "asc" : "desc",
+			tagids: selectedModTags.map((tag) => tag.tagid),
 			versions: selectedGameVersions.map((version) => version),
 		}),
 	);
@@ -101,22 +145,22 @@ export function ModList({
 			}
 			return true;
 		})
+		// Authors picked by clicking their name in the list have no id to send
 		?.filter((mod) => {
-			if (author) {
+			if (author && authorId === null) {
 				return mod.author === author;
 			}
 			return true;
 		})
-		?.filter((mod) => mod.type === category)
-		?.filter((mod) =>
-			side !== "installed"
-				? mod.side === side
-				: installedMods.some(
-						(installedMod) =>
-							installedMod.modid === mod.modid ||
-							installedMod.modid.toString() === mod.urlalias ||
-							mod.modidstrs.includes(installedMod.modid.toString()),
-					),
+		?.filter(
+			(mod) =>
+				side !== "installed" ||
+				installedMods.some(
+					(installedMod) =>
+						installedMod.modid === mod.modid ||
+						installedMod.modid.toString() === mod.urlalias ||
+						mod.modidstrs.includes(installedMod.modid.toString()),
+				),
 		)
 		.sort((a, b) => {
 			if (side === "installed") {
@@ -167,46 +211,8 @@ export function ModList({
 				}
 				return a.name.localeCompare(b.name);
 			}
-			if (sortBy === "updated") {
-				if (orderDirection === "descending") {
-					return (
-						new Date(b.lastreleased).getTime() -
-						new Date(a.lastreleased).getTime()
-					);
-				}
-				return (
-					new Date(a.lastreleased).getTime() -
-					new Date(b.lastreleased).getTime()
-				);
-			}
-			if (sortBy === "downloads") {
-				if (orderDirection === "descending") {
-					return a.downloads - b.downloads;
-				}
-				return b.downloads - a.downloads;
-			}
-			if (sortBy === "follows") {
-				if (orderDirection === "descending") {
-					return a.follows - b.follows;
-				}
-				return b.follows - a.follows;
-			}
-			if (sortBy === "trending") {
-				if (orderDirection === "descending") {
-					return a.trendingpoints - b.trendingpoints;
-				}
-				return b.trendingpoints - a.trendingpoints;
-			}
-			if (sortBy === "comments") {
-				if (orderDirection === "descending") {
-					return a.comments - b.comments;
-				}
-				return b.comments - a.comments;
-			}
-			if (orderDirection === "descending") {
-				return 0;
-			}
-			return -1;
+			// Everything else comes sorted by the api
+			return 0;
 		});
 
 	const estimateSize = useCallback(() => 81, []);
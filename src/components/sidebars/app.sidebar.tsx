@@ -41,6 +41,14 @@ export function AppSidebar() {
 		useAccountStore();
 	const { installations } = useInstallations();
 	const { openDialog } = useDialogStore();
+	const forgetUser = (uid: string | undefined) => {
+		removeUser(uid);
+		if (uid) {
+			invoke("forget_account", { uid }).catch((error) =>
+				toast.error(`Error removing stored session: ${error.message}`),
+			);
+		}
+	};
 	const { mutate: verifyAuth } = useVerifyAuth({
 		onError: (error, variables) => {
 			toast.error(
@@ -127,17 +135,11 @@ export function AppSidebar() {
 											<Button
 												className="flex items-center justify-center hover:text-green-900 p-1 rounded-none"
 												onClick={() => {
-													verifyAuth({
-														sessionkey: user.sessionkey || "",
-														uid: user.uid || "",
-													});
+													verifyAuth({ uid: user.uid || "" });
 												}}
 												onKeyUp={(e) => {
 													if (e.key === "Enter") {
-														verifyAuth({
-															sessionkey: user.sessionkey || "",
-															uid: user.uid || "",
-														});
+														verifyAuth({ uid: user.uid || "" });
 													}
 												}}
 												size="icon"
@@ -155,11 +157,11 @@ export function AppSidebar() {
 											<Button
 												className="flex items-center justify-center hover:text-red-900 p-1 rounded-none group-first:rounded-tr-md"
 												onClick={() => {
-													removeUser(user.uid);
+													forgetUser(user.uid);
 												}}
 												onKeyUp={(e) => {
 													if (e.key === "Enter") {
-														removeUser(user.uid);
+														forgetUser(user.uid);
 													}
 												}}
 												size="icon"
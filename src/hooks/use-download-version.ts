@@ -1,5 +1,6 @@
 import { type UseMutationOptions, useMutation } from "@tanstack/react-query";
 import { invoke } from "@tauri-apps/api/core";
+import type { DownloadInfo } from "@/lib/types";
 import { zipfolderprefix } from "@/lib/utils";
 import { useAppFolder } from "./use-app-folder";
 
@@ -9,13 +10,12 @@ export const useDownloadVersion = (
 	const { appFolder } = useAppFolder();
 	return useMutation({
 		mutationFn: async (version: string) => {
-			const url = (await invoke("get_download_link", {
+			const info = (await invoke("get_download_info", {
 				version,
-			})) as string;
-			if (!url) {
+			})) as DownloadInfo;
+			if (!info.url) {
 				throw new Error("Download URL not found in response");
 			}
-			const downloadUrl = url;
 			if (!appFolder) {
 				throw new Error("App folder not found");
 			}
@@ -24,7 +24,9 @@ export const useDownloadVersion = (
 				emitevent: `download://version:${version.replace(/\./g, "_")}`,
 				extract: true,
 				extractdir: `${appFolder}/versions/${version}`,
-				url: downloadUrl,
+				url: info.url,
+				expectedsize: info.filesize,
+				expectedhash: info.hash,
 				zipsubfolderprefix: zipfolderprefix(),
 			}) as Promise<string>;
 		},
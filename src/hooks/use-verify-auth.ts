@@ -11,11 +11,10 @@ export const useVerifyAuth = (
 			reason: string | null;
 		},
 		Error,
-		{ uid: string; sessionkey: string }
+		{ uid: string }
 	>,
 ) =>
 	useMutation({
-		mutationFn: async ({ uid, sessionkey }) =>
-			invoke("verify", { sessionkey, uid }),
+		mutationFn: async ({ uid }) => invoke("verify", { uid }),
 		...props,
 	});
@@ -8,6 +8,20 @@ export type ProgressPayload = {
 	message: string | null;
 };
 
+// hash is "<algo>:<hex>", checked by download_and_maybe_extract
+export type DownloadInfo = {
+	url: string;
+	filesize: number | null;
+	hash: string | null;
+};
+
+export type UiError = {
+	code: string;
+	message: string;
+	details?: Record<string, unknown>;
+	sources?: string[];
+};
+
 export type ModTag = {
 	tagid: number;
 	name: string;
@@ -27,6 +41,14 @@ export type Release = {
 	changelog: string | null;
 };
 
+export type ModScreenshot = {
+	fileid: number | null;
+	mainfile: string;
+	filename: string;
+	thumbnailfilename: string | null;
+	created: string;
+};
+
 export type ModInfo = {
 	mod: {
 		modid: number;
@@ -54,7 +76,7 @@ export type ModInfo = {
 		lastmodified: string;
 		tags: string[];
 		releases: Release[];
-		screenshots: string[];
+		screenshots: ModScreenshot[];
 	};
 	statuscode: string;
 };
@@ -55,6 +55,8 @@ export type OutputMod = {
 	authors: string[];
 	version: string;
 	path: string;
+	dependencies: Record<string, string>;
+	enabled: boolean;
 };
 
 function RouteComponent() {
@@ -210,7 +212,7 @@ function RouteComponent() {
 					textUnchecked="Desc"
 				/>
 				<AuthorCombobox
-					onChange={(e) => setAuthor(e.target.value)}
+					onAuthorChange={(name, id) => setAuthor(name, id)}
 					value={author}
 				/>
 				<SideToggleGroup />
@@ -5,8 +5,6 @@ export type User = {
 	uid: string | undefined;
 	email: string;
 	playername: string | undefined;
-	sessionkey: string | undefined;
-	sessionsignature: string | undefined;
 };
 
 type AccountStore = {
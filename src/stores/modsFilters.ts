@@ -24,7 +24,12 @@ export type ModsFilters = {
 	orderDirection: "ascending" | "descending";
 	setOrderDirection: (direction: ModsFilters["orderDirection"]) => void;
 	author: string;
-	setAuthor: (author: ModsFilters["author"]) => void;
+	// ModDB user id of the author, only known when picked from the author list
+	authorId: number | null;
+	setAuthor: (
+		author: ModsFilters["author"],
+		authorId?: ModsFilters["authorId"],
+	) => void;
 	side: "client" | "server" | "both" | "installed";
 	setSide: (side: ModsFilters["side"]) => void;
 	category: "mod" | "externaltool" | "other";
@@ -41,6 +46,7 @@ export const useModsFilters = create<ModsFilters>()((set) => ({
 			selectedModTags: [...state.selectedModTags, tag],
 		})),
 	author: "",
+	authorId: null,
 	category: "mod",
 	installedOnly: false,
 	orderDirection: "ascending",
@@ -59,7 +65,7 @@ export const useModsFilters = create<ModsFilters>()((set) => ({
 	searchText: "",
 	selectedGameVersions: [],
 	selectedModTags: [],
-	setAuthor: (author) => set({ author }),
+	setAuthor: (author, authorId = null) => set({ author, authorId }),
 	setCategory: (category) => set({ category }),
 	setOrderDirection: (direction) => set({ orderDirection: direction }),
 	setSearchText: (text) => set({ searchText: text }),